@@ -0,0 +1,187 @@
+/*
+    Copyright (c) 2026 XBID LABS LLC
+
+    This file is part of XBID-AI project.
+    Licensed under the MIT License.
+    Author: Fred Kyung-jin Rezeau (오경진 吳景振) <hello@kyungj.in>
+*/
+
+#![no_std]
+#![cfg(any(test, feature = "testutils"))]
+
+use escrow::{Clock, EmissionParams, Escrow, EscrowClient};
+use soroban_sdk::{testutils::{Address as _, Ledger}, token, Address, Env};
+
+pub const YEAR_LEDGERS: u64 = 6_307_200;
+pub const YEAR_SECONDS: u64 = 31_536_000;
+
+// Builds an escrow initialized on a fresh SAC holding the total supply.
+pub struct EscrowBuilder {
+    clock: Clock,
+    ledger: u32,
+    timestamp: u64,
+    delay: u64,
+    burn_bps: i128,
+}
+
+impl Default for EscrowBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EscrowBuilder {
+    pub fn new() -> Self {
+        Self { clock: Clock::Ledger, ledger: 1_000, timestamp: 1_700_000_000, delay: 0, burn_bps: 0 }
+    }
+
+    // Key the curve on the ledger close timestamp.
+    pub fn timestamp(mut self) -> Self {
+        self.clock = Clock::Timestamp;
+        self
+    }
+
+    // Ledger sequence and close timestamp at initialization.
+    pub fn at(mut self, ledger: u32, timestamp: u64) -> Self {
+        self.ledger = ledger;
+        self.timestamp = timestamp;
+        self
+    }
+
+    // Origin this many clock units after initialization.
+    pub fn delay(mut self, delay: u64) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    // Share of each release burned, set by admin after initialization.
+    pub fn burn_bps(mut self, bps: i128) -> Self {
+        self.burn_bps = bps;
+        self
+    }
+
+    pub fn build(self) -> TestEscrow {
+        let params = EmissionParams::of(self.clock);
+        let env = Env::default();
+        let contract_id = env.register(Escrow, ());
+        let client = EscrowClient::new(&env, &contract_id);
+        let token_admin = Address::generate(&env);
+        let token_id = env.register_stellar_asset_contract_v2(token_admin.clone()).address();
+        let token = token::Client::new(&env, &token_id);
+        let token_admin_client = token::StellarAssetClient::new(&env, &token_id);
+        let admin = Address::generate(&env);
+        let fund = Address::generate(&env);
+        let rewards = Address::generate(&env);
+
+        env.mock_all_auths();
+        env.ledger().set_sequence_number(self.ledger);
+        env.ledger().set_timestamp(self.timestamp);
+        token_admin_client.mint(&admin, &params.total_supply);
+
+        let origin = match self.clock {
+            Clock::Ledger => {
+                let origin = self.ledger as u64 + self.delay;
+                client.initialize(&admin, &token_id, &fund, &rewards, &(origin as u32));
+                origin
+            }
+            Clock::Timestamp => {
+                let origin = self.timestamp + self.delay;
+                client.initialize_timestamp(&admin, &token_id, &fund, &rewards, &origin);
+                origin
+            }
+        };
+
+        if self.burn_bps > 0 {
+            client.set_burn_bps(&self.burn_bps);
+        }
+
+        TestEscrow {
+            env,
+            client,
+            contract_id,
+            token,
+            token_admin: token_admin_client,
+            admin,
+            fund,
+            rewards,
+            clock: self.clock,
+            params,
+            origin,
+        }
+    }
+}
+
+pub struct TestEscrow {
+    pub env: Env,
+    pub client: EscrowClient<'static>,
+    pub contract_id: Address,
+    pub token: token::Client<'static>,
+    pub token_admin: token::StellarAssetClient<'static>,
+    pub admin: Address,
+    pub fund: Address,
+    pub rewards: Address,
+    pub clock: Clock,
+    pub params: EmissionParams,
+    pub origin: u64,
+}
+
+impl TestEscrow {
+    // Current position on the escrow clock.
+    pub fn now(&self) -> u64 {
+        match self.clock {
+            Clock::Ledger => self.env.ledger().sequence() as u64,
+            Clock::Timestamp => self.env.ledger().timestamp(),
+        }
+    }
+
+    // Advance the escrow clock; the other clock follows at 5s per ledger.
+    pub fn advance(&self, units: u64) {
+        let (ledgers, seconds) = match self.clock {
+            Clock::Ledger => (units, units * 5),
+            Clock::Timestamp => (units / 5, units),
+        };
+
+        self.env.ledger().with_mut(|ledger| {
+            ledger.sequence_number += ledgers as u32;
+            ledger.timestamp += seconds;
+        });
+    }
+
+    pub fn advance_years(&self, years: u64) {
+        self.advance(years * match self.clock {
+            Clock::Ledger => YEAR_LEDGERS,
+            Clock::Timestamp => YEAR_SECONDS,
+        });
+    }
+
+    // Total released to date, allocation excluded, burned share included.
+    pub fn emitted(&self) -> i128 {
+        self.token.balance(&self.fund) + self.token.balance(&self.rewards) + self.client.burned()
+            - self.params.allocation
+    }
+
+    // Release, asserting the amount matches the forecast, the split
+    // follows fund_bps and burn_bps and the supply is conserved.
+    pub fn release_and_assert(&self) -> i128 {
+        let expected = self.client.forecast(&0);
+        let fund = self.token.balance(&self.fund);
+        let rewards = self.token.balance(&self.rewards);
+        let escrow = self.token.balance(&self.contract_id);
+
+        let delta = self.client.release();
+        assert_eq!(delta, expected, "release differs from forecast");
+
+        let fund_share = delta * self.params.fund_bps / 10_000;
+        let burn = delta * self.client.burn_bps() / 10_000;
+        assert_eq!(self.token.balance(&self.fund) - fund, fund_share, "fund share");
+        assert_eq!(self.token.balance(&self.rewards) - rewards, delta - fund_share - burn, "rewards share");
+        assert_eq!(escrow - self.token.balance(&self.contract_id), delta, "escrow outflow");
+        assert_eq!(self.token.balance(&self.fund) + self.token.balance(&self.rewards)
+            + self.token.balance(&self.contract_id), self.params.total_supply - self.client.burned(), "supply");
+
+        delta
+    }
+}
+
+#[cfg(test)]
+mod test;
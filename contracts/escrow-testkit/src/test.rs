@@ -0,0 +1,60 @@
+/*
+    Copyright (c) 2026 XBID LABS LLC
+
+    This file is part of XBID-AI project.
+    Licensed under the MIT License.
+    Author: Fred Kyung-jin Rezeau (오경진 吳景振) <hello@kyungj.in>
+*/
+
+use crate::*;
+
+#[test]
+fn test_builder() {
+    let escrow = EscrowBuilder::new().build();
+    assert_eq!(escrow.origin, 1_000);
+    assert_eq!(escrow.token.balance(&escrow.admin), 0);
+    assert_eq!(escrow.token.balance(&escrow.fund), escrow.params.allocation);
+    assert_eq!(escrow.token.balance(&escrow.contract_id), escrow.params.pool());
+    assert_eq!(escrow.release_and_assert(), 0);
+
+    // Half the pool is out after one half-life.
+    escrow.advance_years(7);
+    assert!(escrow.release_and_assert() > 0);
+    assert_eq!(escrow.emitted(), escrow.params.pool() / 2);
+    assert_eq!(escrow.release_and_assert(), 0);
+}
+
+#[test]
+fn test_builder_timestamp() {
+    let escrow = EscrowBuilder::new().timestamp().delay(3_600).build();
+    assert_eq!(escrow.origin, 1_700_003_600);
+
+    escrow.advance(3_600);
+    assert_eq!(escrow.release_and_assert(), 0);
+
+    let mut released = 0;
+    for _ in 0..7 {
+        escrow.advance_years(1);
+        released += escrow.release_and_assert();
+    }
+
+    assert_eq!(escrow.now(), escrow.origin + 7 * YEAR_SECONDS);
+    assert_eq!(released, escrow.emitted());
+    assert_eq!(released, escrow.params.pool() / 2);
+}
+
+#[test]
+fn test_builder_burn() {
+    let escrow = EscrowBuilder::new().burn_bps(2_500).build();
+    assert_eq!(escrow.client.burn_bps(), 2_500);
+
+    let mut released = 0;
+    for _ in 0..7 {
+        escrow.advance_years(1);
+        released += escrow.release_and_assert();
+    }
+
+    assert_eq!(released, escrow.emitted());
+    assert_eq!(released, escrow.params.pool() / 2);
+    assert!(escrow.client.burned() > 0);
+}
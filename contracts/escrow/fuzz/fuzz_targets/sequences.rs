@@ -0,0 +1,284 @@
+/*
+    Copyright (c) 2026 XBID LABS LLC
+
+    This file is part of XBID-AI project.
+    Licensed under the MIT License.
+    Author: Fred Kyung-jin Rezeau (오경진 吳景振) <hello@kyungj.in>
+*/
+
+// Drives arbitrary entrypoint sequences against the escrow and checks
+// that supply is conserved (burns included) and escrowed tokens only
+// reach the fund/rewards pairs of the curve and tranches.
+// Build the contract wasm first (`make build`), then `cargo fuzz run sequences`.
+
+#![no_main]
+
+use arbitrary::Arbitrary;
+use escrow::{EmissionParams, Escrow, EscrowClient, SurplusPolicy};
+use libfuzzer_sys::fuzz_target;
+use soroban_sdk::{testutils::{Address as _, IssuerFlags, Ledger}, token, Address, Env};
+
+const WASM: &[u8] = include_bytes!("../../../../target/wasm32-unknown-unknown/release/escrow.wasm");
+
+const TOTAL_SUPPLY: i128 = EmissionParams::default().total_supply;
+const ALLOCATION: i128 = EmissionParams::default().allocation;
+const TRANCHES: u32 = 4;
+
+#[derive(Arbitrary, Debug, Clone, Copy)]
+enum Holder {
+    Escrow,
+    Fund,
+    Rewards,
+}
+
+#[derive(Arbitrary, Debug)]
+enum Op {
+    Initialize { timestamp: bool, offset: u32 },
+    Release,
+    Advance { ledgers: u32, seconds: u32 },
+    Upgrade,
+    Pause,
+    Unpause,
+    Deposit { holder: Holder, amount: u64 },
+    Authorize { holder: Holder, authorized: bool },
+    Reconcile,
+    SetPolicy { policy: u8 },
+    TopUp { amount: u64 },
+    SetHalfLife { half_life: i64 },
+    SetBurn { bps: u16 },
+    Stray { amount: u64 },
+    Sweep { partner: bool },
+    AddTranche { id: u8, partner: bool, pool: u64, offset: u32, half_life: i64 },
+    ReleaseTranche { id: u8 },
+    ReleaseToken { partner: bool },
+    ReleaseAll,
+}
+
+fuzz_target!(|ops: Vec<Op>| {
+    let env = Env::default();
+    let contract_id = env.register(Escrow, ());
+    let client = EscrowClient::new(&env, &contract_id);
+    let token_admin = Address::generate(&env);
+    let sac = env.register_stellar_asset_contract_v2(token_admin.clone());
+    let token_id = sac.address();
+    let token = token::Client::new(&env, &token_id);
+    let token_admin_client = token::StellarAssetClient::new(&env, &token_id);
+    let admin = Address::generate(&env);
+    let fund = Address::generate(&env);
+    let rewards = Address::generate(&env);
+    let outsider = Address::generate(&env);
+    let recipients: [(Address, Address); TRANCHES as usize] =
+        core::array::from_fn(|_| (Address::generate(&env), Address::generate(&env)));
+    let partner_id = env.register_stellar_asset_contract_v2(token_admin.clone()).address();
+    let partner = token::Client::new(&env, &partner_id);
+    let partner_admin_client = token::StellarAssetClient::new(&env, &partner_id);
+    let stray_id = env.register_stellar_asset_contract_v2(token_admin.clone()).address();
+    let stray = token::Client::new(&env, &stray_id);
+    let stray_admin_client = token::StellarAssetClient::new(&env, &stray_id);
+
+    env.mock_all_auths();
+    sac.issuer().set_flag(IssuerFlags::RevocableFlag);
+    env.ledger().set_sequence_number(1_000);
+    env.ledger().set_timestamp(1_700_000_000);
+    token_admin_client.mint(&admin, &TOTAL_SUPPLY);
+
+    let address = |holder: Holder| match holder {
+        Holder::Escrow => contract_id.clone(),
+        Holder::Fund => fund.clone(),
+        Holder::Rewards => rewards.clone(),
+    };
+
+    // Balance held by fund, rewards and every tranche's own pair.
+    let held = |token: &token::Client| {
+        recipients.iter().fold(token.balance(&fund) + token.balance(&rewards),
+            |held, (fund, rewards)| held + token.balance(fund) + token.balance(rewards))
+    };
+
+    let mut minted = TOTAL_SUPPLY;
+    let mut partner_minted = 0i128;
+    let mut stray_minted = 0i128;
+    let mut paid = 0i128;
+    let mut pooled = TOTAL_SUPPLY - ALLOCATION;
+    let mut policy = SurplusPolicy::Fund;
+    let mut initialized = false;
+    for op in ops.iter().take(64) {
+        let before = held(&token);
+        let burned = if initialized { client.burned() } else { 0 };
+        match *op {
+            Op::Initialize { timestamp, offset } => {
+                let initialize = if timestamp {
+                    let origin = env.ledger().timestamp() + (offset % 86_400) as u64;
+                    client.try_initialize_timestamp(&admin, &token_id, &fund, &rewards, &origin)
+                } else {
+                    let origin = env.ledger().sequence() + offset % 17_280;
+                    client.try_initialize(&admin, &token_id, &fund, &rewards, &origin)
+                };
+                if initialize.is_ok() {
+                    assert!(!initialized, "initialized twice");
+                    initialized = true;
+                    paid += ALLOCATION;
+                }
+            }
+            Op::Release => {
+                let release = client.try_release();
+                if let Ok(Ok(delta)) = release {
+                    assert!(initialized && delta >= 0);
+                    paid += delta - (client.burned() - burned);
+                }
+            }
+            Op::Advance { ledgers, seconds } => {
+                env.ledger().with_mut(|ledger| {
+                    ledger.sequence_number = ledger.sequence_number.saturating_add(ledgers % 10_000_000);
+                    ledger.timestamp = ledger.timestamp.saturating_add(seconds as u64 * 1_000);
+                });
+            }
+            Op::Upgrade => {
+                let hash = env.deployer().upload_contract_wasm(WASM);
+                if client.try_upgrade(&hash).is_ok() {
+                    assert!(initialized);
+                    assert!(client.try_migrate().is_err());
+                }
+            }
+            Op::Pause => {
+                let _ = client.try_pause();
+            }
+            Op::Unpause => {
+                let _ = client.try_unpause();
+            }
+            Op::Deposit { holder, amount } => {
+                let amount = amount as i128;
+                token_admin_client.mint(&outsider, &amount);
+                if token.try_transfer(&outsider, &address(holder), &amount).is_ok() {
+                    minted += amount;
+                    if !matches!(holder, Holder::Escrow) {
+                        paid += amount;
+                    }
+                } else {
+                    token_admin_client.burn(&outsider, &amount);
+                }
+            }
+            Op::Authorize { holder, authorized } => {
+                token_admin_client.set_authorized(&address(holder), &authorized);
+            }
+            Op::Reconcile => {
+                if let Ok(Ok(amount)) = client.try_reconcile() {
+                    assert!(initialized && amount >= 0);
+                    match policy {
+                        SurplusPolicy::TopUp => pooled += amount,
+                        _ => paid += amount,
+                    }
+                }
+            }
+            Op::SetPolicy { policy: index } => {
+                let next = match index % 3 {
+                    0 => SurplusPolicy::Fund,
+                    1 => SurplusPolicy::Rewards,
+                    _ => SurplusPolicy::TopUp,
+                };
+                if client.try_set_surplus_policy(&next).is_ok() {
+                    policy = next;
+                }
+            }
+            Op::TopUp { amount } => {
+                let amount = amount as i128;
+                token_admin_client.mint(&admin, &amount);
+                if client.try_top_up(&admin, &amount).is_ok() {
+                    assert!(initialized);
+                    minted += amount;
+                    pooled += amount;
+                } else {
+                    token_admin_client.burn(&admin, &amount);
+                }
+            }
+            Op::SetHalfLife { half_life } => {
+                let _ = client.try_set_half_life(&(half_life as i128));
+            }
+            Op::SetBurn { bps } => {
+                let _ = client.try_set_burn_bps(&(bps as i128));
+            }
+            Op::Stray { amount } => {
+                let amount = amount as i128;
+                stray_admin_client.mint(&contract_id, &amount);
+                stray_minted += amount;
+            }
+            Op::Sweep { partner } => {
+                let swept = if partner { &partner_id } else { &stray_id };
+                assert!(client.try_sweep(&token_id).is_err());
+                if let Ok(Ok(amount)) = client.try_sweep(swept) {
+                    assert!(amount >= 0);
+                }
+            }
+            Op::AddTranche { id, partner, pool, offset, half_life } => {
+                // Escrow-token tranches are carved from the primary pool,
+                // partner tranches funded by admin.
+                let pool = pool as i128;
+                let tranche_token = if partner { &partner_id } else { &token_id };
+                if partner {
+                    partner_admin_client.mint(&admin, &pool);
+                }
+
+                let id = id as u32 % TRANCHES;
+                let (tranche_fund, tranche_rewards) = &recipients[id as usize];
+                let origin = env.ledger().sequence() as u64 + (offset % 17_280) as u64;
+                let added = client.try_add_tranche(&id, tranche_token, tranche_fund, tranche_rewards,
+                    &pool, &origin, &(half_life as i128));
+                if added.is_ok() {
+                    assert!(initialized);
+                    if partner {
+                        partner_minted += pool;
+                    }
+                } else if partner {
+                    partner_admin_client.burn(&admin, &pool);
+                }
+            }
+            Op::ReleaseTranche { id } => {
+                let id = id as u32 % TRANCHES;
+                if let Ok(Ok(delta)) = client.try_release_tranche(&id) {
+                    assert!(delta >= 0);
+                    if client.tranche(&id).token == token_id {
+                        paid += delta - (client.burned() - burned);
+                    }
+                }
+            }
+            Op::ReleaseToken { partner } => {
+                let released_token = if partner { &partner_id } else { &token_id };
+                if let Ok(Ok(total)) = client.try_release_token(released_token) {
+                    assert!(total >= 0);
+                    if !partner {
+                        paid += total - (client.burned() - burned);
+                    }
+                }
+            }
+            Op::ReleaseAll => {
+                if let Ok(Ok(totals)) = client.try_release_all() {
+                    let total = totals.get(token_id.clone()).unwrap_or(0);
+                    assert!(total >= 0);
+                    paid += total - (client.burned() - burned);
+                }
+            }
+        }
+
+        // Supply is conserved, burns included, and nobody else holds tokens.
+        let escrow = token.balance(&contract_id);
+        let held_balance = held(&token);
+        let admin_balance = token.balance(&admin);
+        let burned = if initialized { client.burned() } else { 0 };
+        assert_eq!(escrow + held_balance + admin_balance + burned, minted);
+        assert_eq!(partner.balance(&contract_id) + held(&partner), partner_minted);
+        assert_eq!(stray.balance(&contract_id) + stray.balance(&fund), stray_minted);
+        assert_eq!(token.balance(&outsider), 0);
+        assert_eq!(admin_balance, if initialized { 0 } else { TOTAL_SUPPLY });
+
+        // Escrowed tokens only flow to fund/rewards pairs, by the amounts reported.
+        assert!(held_balance >= before);
+        assert_eq!(held_balance, paid);
+        if initialized {
+            let emitted = match client.release_count() {
+                0 => 0,
+                count => client.releases(&(count - 1), &1).get(0).unwrap().cumulative,
+            };
+            assert!(emitted <= pooled);
+            assert!(escrow >= client.locked(&token_id));
+        }
+    }
+});
@@ -6,22 +6,157 @@
     Author: Fred Kyung-jin Rezeau (오경진 吳景振) <hello@kyungj.in>
 */
 
-use crate::types::EmissionParams;
+use crate::types::{EmissionParams, Error};
 
-// Curve: Δ = F(target) − realized.
-pub fn delta(origin: u32, target: u32, realized: i128) -> i128 {
+// Bounds within which the curve never overflows: pool * half_life
+// stays below 2^126 and half_life + elapsed below 2^65. Parameters
+// outside return Error::ArithmeticOverflow instead of trapping.
+// pool: 0..=MAX_POOL (i64::MAX, the classic asset supply limit).
+// half_life: 1..=MAX_HALF_LIFE, in clock units.
+// origin, target, paused: any u64 (u32::MAX ledgers included).
+// realized: 0..=pool.
+pub const MAX_POOL: i128 = i64::MAX as i128;
+pub const MAX_HALF_LIFE: i128 = i64::MAX as i128;
+
+// Shipped parameters must sit inside the bounds, checked at compile time.
+const _: () = {
+    let (ledger, timestamp) = (EmissionParams::default(), EmissionParams::timestamp());
+    assert!(ledger.pool() <= MAX_POOL && ledger.half_life <= MAX_HALF_LIFE);
+    assert!(timestamp.pool() <= MAX_POOL && timestamp.half_life <= MAX_HALF_LIFE);
+};
+
+// Curve: Δ = F(target − paused) − realized.
+// Origin, target, paused and half-life share the same clock units.
+pub fn delta(pool: i128, half_life: i128, origin: u64, target: u64,
+    paused: u64, realized: i128) -> Result<i128, Error> {
     if target <= origin { // Never emit backward.
-        return 0;
+        return Ok(0);
     }
 
-    let params = EmissionParams::default();
-    let pool = params.pool();
-    let elapsed = (target - origin) as i128;
-    let delta = (pool - ((pool * params.half_life)
-        / (params.half_life + elapsed))) - realized;
+    let elapsed = (target - origin).saturating_sub(paused) as i128;
+    let scaled = pool.checked_mul(half_life).ok_or(Error::ArithmeticOverflow)?;
+    let span = half_life.checked_add(elapsed).ok_or(Error::ArithmeticOverflow)?;
+    let remaining = scaled.checked_div(span).ok_or(Error::ArithmeticOverflow)?;
+    let delta = pool.checked_sub(remaining)
+        .and_then(|emitted| emitted.checked_sub(realized))
+        .ok_or(Error::ArithmeticOverflow)?;
     if delta <= 0 {
-        return 0;
+        return Ok(0);
+    }
+
+    Ok(delta)
+}
+
+// Inverse of the curve: elapsed time at which a curve over `pool` has
+// emitted `emitted`, rounded down so that
+// delta(elapsed) <= emitted < delta(elapsed + 1).
+// Rebasing onto a larger pool at this elapsed time keeps emitted-to-date
+// continuous and spreads the extra supply over the remaining tail.
+pub fn elapsed(pool: i128, half_life: i128, emitted: i128) -> Result<u64, Error> {
+    if emitted < 0 || emitted >= pool {
+        return Err(Error::ArithmeticOverflow);
+    }
+
+    // Solve pool·H/(H+τ) = pool − emitted for τ.
+    let scaled = pool.checked_mul(half_life).ok_or(Error::ArithmeticOverflow)?;
+    let span = scaled / (pool - emitted);
+    u64::try_from(span - half_life).map_err(|_| Error::ArithmeticOverflow)
+}
+
+// Bounded model checking of the curve invariants: `cargo kani`, run by
+// the kani workflow. The curve has no loops, a minimal unwind suffices.
+#[cfg(kani)]
+mod proofs {
+    use super::*;
+
+    // Clock range checked: 2^40 units, over 34,000 years of seconds.
+    const HORIZON: u64 = 1 << 40;
+
+    // A clock position within the horizon.
+    fn at() -> u64 {
+        let at: u64 = kani::any();
+        kani::assume(at <= HORIZON);
+        at
+    }
+
+    // Pools up to twice the shipped one (top-ups, tranches carved out).
+    const POOL: i128 = 2 * EmissionParams::default().pool();
+
+    // Half-lives one retune step around the shipped ledger and
+    // timestamp values.
+    const HALF_LIFE: (i128, i128) = {
+        let (ledger, timestamp) = (EmissionParams::default(), EmissionParams::timestamp());
+        (ledger.half_life - ledger.half_life * ledger.half_life_step_bps / 10000,
+            timestamp.half_life + timestamp.half_life * timestamp.half_life_step_bps / 10000)
+    };
+
+    // Curve parameters around the shipped EmissionParams.
+    fn params() -> (i128, i128, u64, u64) {
+        let (pool, half_life) = (kani::any(), kani::any());
+        kani::assume((0..=POOL).contains(&pool));
+        kani::assume((HALF_LIFE.0..=HALF_LIFE.1).contains(&half_life));
+        (pool, half_life, at(), at())
     }
 
-    delta
-}
\ No newline at end of file
+    fn emitted(pool: i128, half_life: i128, origin: u64, target: u64, paused: u64) -> i128 {
+        delta(pool, half_life, origin, target, paused, 0).unwrap()
+    }
+
+    #[kani::proof]
+    #[kani::unwind(2)]
+    #[kani::solver(cadical)]
+    fn bounded() {
+        let (pool, half_life, origin, paused) = params();
+        let (target, realized): (u64, i128) = (at(), kani::any());
+        kani::assume((0..=pool).contains(&realized));
+
+        let emitted = emitted(pool, half_life, origin, target, paused);
+        assert!((0..=pool).contains(&emitted));
+
+        let delta = delta(pool, half_life, origin, target, paused, realized).unwrap();
+        assert!((0..=pool - realized).contains(&delta));
+    }
+
+    #[kani::proof]
+    #[kani::unwind(2)]
+    #[kani::solver(cadical)]
+    fn monotonic() {
+        let (pool, half_life, origin, paused) = params();
+        let (earlier, later) = (at(), at());
+        kani::assume(earlier <= later);
+
+        assert!(emitted(pool, half_life, origin, earlier, paused)
+            <= emitted(pool, half_life, origin, later, paused));
+    }
+
+    #[kani::proof]
+    #[kani::unwind(2)]
+    #[kani::solver(cadical)]
+    fn frequency_independent() {
+        // Releasing at `split` then at `target` pays what one release at `target` does.
+        let (pool, half_life, origin, paused) = params();
+        let (split, target) = (at(), at());
+        kani::assume(split <= target);
+
+        let first = emitted(pool, half_life, origin, split, paused);
+        let second = delta(pool, half_life, origin, target, paused, first).unwrap();
+        assert_eq!(first + second, emitted(pool, half_life, origin, target, paused));
+    }
+
+    #[kani::proof]
+    #[kani::unwind(2)]
+    #[kani::solver(cadical)]
+    fn rebase_continuous() {
+        // The rebased curve brackets emitted-to-date within one clock unit.
+        let (pool, half_life, _, _) = params();
+        let emitted: i128 = kani::any();
+        kani::assume((0..pool).contains(&emitted));
+
+        if let Ok(elapsed) = elapsed(pool, half_life, emitted) {
+            assert!(super::delta(pool, half_life, 0, elapsed, 0, 0).unwrap() <= emitted);
+            if elapsed < u64::MAX {
+                assert!(super::delta(pool, half_life, 0, elapsed + 1, 0, 0).unwrap() > emitted);
+            }
+        }
+    }
+}
@@ -6,15 +6,53 @@
     Author: Fred Kyung-jin Rezeau (오경진 吳景振) <hello@kyungj.in>
 */
 
-use crate::{storage, emission, types::{Error, EmissionParams}};
-use soroban_sdk::{token, Address, BytesN, Env};
+use crate::{storage, emission, types::{Checkpoint, Clock, Cliff, Error,
+    EmissionParams, Release, ReleaseCap, ReleaseThreshold, State, Storage,
+    SurplusPolicy, Tranche, TrancheReleased}};
+use soroban_sdk::{token, Address, BytesN, Env, Map, TryFromVal, Val, Vec};
+
+const MAX_PAGE: u32 = 100;
+
+// Entries of each kind extended per bump_history call, within the
+// transaction footprint limits.
+const MAX_BUMP: u32 = 20;
+
+// Tranches are kept in instance storage and walked by release_all.
+const MAX_TRANCHES: u32 = 16;
 
 pub fn initialize(env: &Env, admin: Address, token: Address,
     fund: Address, rewards: Address, ledger: u32) -> Result<(), Error> {
-    if storage::has_token(env) {
+    setup(env, admin, token, fund, rewards, Clock::Ledger, ledger as u64)
+}
+
+pub fn initialize_timestamp(env: &Env, admin: Address, token: Address,
+    fund: Address, rewards: Address, timestamp: u64) -> Result<(), Error> {
+    setup(env, admin, token, fund, rewards, Clock::Timestamp, timestamp)
+}
+
+// Origin must be recent (within tolerance) and not beyond the horizon.
+fn check_origin(env: &Env, clock: Clock, origin: u64) -> Result<(), Error> {
+    let params = EmissionParams::of(clock);
+    let now = now(env, clock);
+    if origin.saturating_add(params.origin_tolerance) < now {
+        return Err(Error::OriginTooEarly);
+    }
+
+    if origin > now.saturating_add(params.origin_horizon) {
+        return Err(Error::OriginTooLate);
+    }
+
+    Ok(())
+}
+
+fn setup(env: &Env, admin: Address, token: Address, fund: Address,
+    rewards: Address, clock: Clock, origin: u64) -> Result<(), Error> {
+    if storage::has_admin(env) {
         return Err(Error::AlreadyInitialized);
     }
 
+    check_origin(env, clock, origin)?;
+
     // Provenance requirement checks (addresses).
     if admin == fund || admin == rewards || fund == rewards {
         return Err(Error::InvalidAddress);
@@ -31,48 +69,626 @@ pub fn initialize(env: &Env, admin: Address, token: Address,
 
     admin.require_auth();
 
-    client.transfer(&admin, &env.current_contract_address(), &params.total_supply);
-    client.transfer(&env.current_contract_address(), &fund, &params.allocation);
+    client.transfer(&admin, &self_addr, &params.total_supply);
+    client.transfer(&self_addr, &fund, &params.allocation);
 
     storage::set_admin(env, &admin);
-    storage::set_token(env, &token);
-    storage::set_fund(env, &fund);
-    storage::set_rewards(env, &rewards);
-    storage::set_ledger(env, ledger);
-    storage::set_emitted(env, 0);
-    storage::extend_ttl(env);
+    storage::set_state(env, &initial(token, fund, rewards, clock, origin));
+    storage::set_version(env, storage::VERSION);
+    storage::extend_ttl(env, storage::TTL_THRESHOLD);
 
     Ok(())
 }
 
-pub fn release(env: &Env) -> i128 {
-    let origin = storage::get_ledger(env);
-    let target = env.ledger().sequence();
-    let realized = storage::get_emitted(env);
-    let delta = emission::delta(origin, target, realized);
+// State of a freshly initialized escrow.
+fn initial(token: Address, fund: Address, rewards: Address, clock: Clock,
+    origin: u64) -> State {
+    let params = EmissionParams::of(clock);
+    State {
+        token,
+        fund,
+        rewards,
+        clock,
+        origin,
+        pool: params.pool(),
+        half_life: params.half_life,
+        shift: 0,
+        carry: 0,
+        retuned_at: None,
+        cliff: Cliff::NONE,
+        cap: ReleaseCap::NONE,
+        threshold: ReleaseThreshold::NONE,
+        surplus: SurplusPolicy::Fund,
+        burn_bps: 0,
+        ttl_threshold: storage::TTL_THRESHOLD,
+        paused: 0,
+        paused_at: None,
+        last_release: None,
+        emitted: 0,
+        burned: 0,
+        releases: 0,
+        checkpoints: 0,
+    }
+}
+
+pub fn release(env: &Env) -> Result<i128, Error> {
+    let mut state = storage::get_state(env);
+    if state.paused_at.is_some() {
+        return Ok(0);
+    }
+
+    let now = now(env, state.clock);
+    let delta = payable(&state, now)?;
     if delta == 0 {
-        return 0;
+        return Ok(0);
     }
 
-    let params = EmissionParams::default();
-    let fund = (delta * params.fund_bps) / 10000;
-    let rewards = delta - fund;
-    let client = token::Client::new(env, &storage::get_token(env));
-    client.transfer(
-        &env.current_contract_address(),
-        &storage::get_fund(env),
-        &fund,
-    );
-    client.transfer(
-        &env.current_contract_address(),
-        &storage::get_rewards(env),
-        &rewards,
-    );
-
-    storage::set_emitted(env, realized + delta);
-    storage::extend_ttl(env);
-
-    delta
+    let params = EmissionParams::of(state.clock);
+    let fund = delta.checked_mul(params.fund_bps)
+        .ok_or(Error::ArithmeticOverflow)? / 10000;
+    let burn = delta.checked_mul(state.burn_bps)
+        .ok_or(Error::ArithmeticOverflow)? / 10000;
+    let rewards = delta - fund - burn;
+    let client = token::Client::new(env, &state.token);
+    let self_addr = env.current_contract_address();
+    client.transfer(&self_addr, &state.fund, &fund);
+    client.transfer(&self_addr, &state.rewards, &rewards);
+    if burn > 0 {
+        client.burn(&self_addr, &burn);
+    }
+
+    state.emitted = state.emitted.checked_add(delta)
+        .ok_or(Error::ArithmeticOverflow)?;
+    state.burned = state.burned.checked_add(burn)
+        .ok_or(Error::ArithmeticOverflow)?;
+    storage::set_release(env, state.releases, &Release {
+        ledger: env.ledger().sequence(),
+        timestamp: env.ledger().timestamp(),
+        delta,
+        fund,
+        rewards,
+        burn,
+        cumulative: state.emitted,
+    });
+    state.releases += 1;
+    state.last_release = Some(now);
+    checkpoint(env, &mut state, now);
+    storage::set_state(env, &state);
+    storage::extend_ttl(env, state.ttl_threshold);
+
+    Ok(delta)
+}
+
+// Add a tranche of `token` with `pool`. Admin only. Escrow-token
+// tranches are carved out of the primary pool, whose curve is rebased
+// down, so the locked supply is unchanged; other tokens are funded by
+// admin, and exactly `pool` must arrive. Either way the locked balance
+// of the token covers every pool in it once the tranche is in.
+#[allow(clippy::too_many_arguments)]
+pub fn add_tranche(env: &Env, id: u32, token: Address, fund: Address,
+    rewards: Address, pool: i128, origin: u64, half_life: i128) -> Result<(), Error> {
+    let admin = storage::get_admin(env);
+    admin.require_auth();
+
+    let mut ids = storage::get_tranches(env);
+    if ids.contains(id) {
+        return Err(Error::TrancheExists);
+    }
+
+    if ids.len() >= MAX_TRANCHES {
+        return Err(Error::TooManyTranches);
+    }
+
+    if fund == rewards {
+        return Err(Error::InvalidAddress);
+    }
+
+    if pool <= 0 || pool > emission::MAX_POOL
+        || half_life <= 0 || half_life > emission::MAX_HALF_LIFE {
+        return Err(Error::InvalidTranche);
+    }
+
+    let mut state = storage::get_state(env);
+    check_origin(env, state.clock, origin)?;
+
+    let now = now(env, state.clock);
+    let client = token::Client::new(env, &token);
+    let self_addr = env.current_contract_address();
+    let locked_before = locked(env, &state, &token)?;
+    if token == state.token {
+        // The primary curve keeps what it has emitted to date.
+        let remaining = state.pool.checked_sub(delta(&state, now, 0)?)
+            .ok_or(Error::ArithmeticOverflow)?;
+        if pool >= remaining {
+            return Err(Error::InvalidTranche);
+        }
+
+        let (primary, half_life) = (state.pool - pool, state.half_life);
+        rebase(&mut state, now, primary, half_life)?;
+        checkpoint(env, &mut state, now);
+        storage::set_state(env, &state);
+    } else {
+        let balance = client.balance(&self_addr);
+        client.transfer(&admin, &self_addr, &pool);
+        if client.balance(&self_addr).checked_sub(balance) != Some(pool) {
+            return Err(Error::InvalidSupply);
+        }
+    }
+
+    storage::set_tranche(env, id, &Tranche {
+        token: token.clone(),
+        fund,
+        rewards,
+        origin,
+        pool,
+        half_life,
+        paused: paused(&state, now),
+        emitted: 0,
+        releases: 0,
+    });
+    ids.push_back(id);
+    storage::set_tranches(env, &ids);
+
+    let locked = locked(env, &state, &token)?;
+    let added = if token == state.token { 0 } else { pool };
+    if locked_before.checked_add(added) != Some(locked) || client.balance(&self_addr) < locked {
+        return Err(Error::InvalidSupply);
+    }
+
+    storage::extend_ttl(env, state.ttl_threshold);
+
+    Ok(())
+}
+
+pub fn release_tranche(env: &Env, id: u32) -> Result<i128, Error> {
+    let mut state = storage::get_state(env);
+    let burned = state.burned;
+    let mut tranche = storage::get_tranche(env, id).ok_or(Error::TrancheNotFound)?;
+    let delta = pay_tranche(env, &mut state, id, &mut tranche)?;
+    if delta > 0 {
+        storage::set_tranche(env, id, &tranche);
+        if state.burned != burned {
+            storage::set_state(env, &state);
+        }
+
+        storage::extend_ttl(env, state.ttl_threshold);
+    }
+
+    Ok(delta)
+}
+
+// Release the primary curve and every tranche; totals per token.
+pub fn release_all(env: &Env) -> Result<Map<Address, i128>, Error> {
+    let mut totals = Map::new(env);
+    totals.set(storage::get_state(env).token, release(env)?);
+
+    let mut state = storage::get_state(env);
+    let burned = state.burned;
+    for id in storage::get_tranches(env).iter() {
+        let mut tranche = storage::get_tranche(env, id).ok_or(Error::TrancheNotFound)?;
+        let delta = pay_tranche(env, &mut state, id, &mut tranche)?;
+        if delta > 0 {
+            storage::set_tranche(env, id, &tranche);
+        }
+
+        let total = totals.get(tranche.token.clone()).unwrap_or(0).checked_add(delta)
+            .ok_or(Error::ArithmeticOverflow)?;
+        totals.set(tranche.token, total);
+    }
+
+    if state.burned != burned {
+        storage::set_state(env, &state);
+    }
+
+    storage::extend_ttl(env, state.ttl_threshold);
+
+    Ok(totals)
+}
+
+// Release the tranches of `token`, the primary curve included when it
+// is the escrow token.
+pub fn release_token(env: &Env, token: Address) -> Result<i128, Error> {
+    let mut total = 0;
+    if token == storage::get_state(env).token {
+        total = release(env)?;
+    }
+
+    let mut state = storage::get_state(env);
+    let burned = state.burned;
+    for id in storage::get_tranches(env).iter() {
+        let mut tranche = storage::get_tranche(env, id).ok_or(Error::TrancheNotFound)?;
+        if tranche.token != token {
+            continue;
+        }
+
+        let delta = pay_tranche(env, &mut state, id, &mut tranche)?;
+        if delta > 0 {
+            storage::set_tranche(env, id, &tranche);
+            total = total.checked_add(delta).ok_or(Error::ArithmeticOverflow)?;
+        }
+    }
+
+    if state.burned != burned {
+        storage::set_state(env, &state);
+    }
+
+    storage::extend_ttl(env, state.ttl_threshold);
+
+    Ok(total)
+}
+
+// Balance of `token` the escrow still owes across its schedules.
+pub fn locked_of(env: &Env, token: Address) -> Result<i128, Error> {
+    locked(env, &storage::get_state(env), &token)
+}
+
+pub fn tranche(env: &Env, id: u32) -> Result<Tranche, Error> {
+    storage::get_tranche(env, id).ok_or(Error::TrancheNotFound)
+}
+
+pub fn tranches(env: &Env) -> Vec<u32> {
+    storage::get_tranches(env)
+}
+
+pub fn tranche_releases(env: &Env, id: u32, from: u32, limit: u32) -> Result<Vec<Release>, Error> {
+    let tranche = storage::get_tranche(env, id).ok_or(Error::TrancheNotFound)?;
+    let end = tranche.releases.min(from.saturating_add(limit.min(MAX_PAGE)));
+    let mut page = Vec::new(env);
+    for index in from..end {
+        page.push_back(storage::get_tranche_release(env, id, index));
+    }
+
+    Ok(page)
+}
+
+pub fn release_count(env: &Env) -> u32 {
+    storage::get_state(env).releases
+}
+
+pub fn releases(env: &Env, from: u32, limit: u32) -> Vec<Release> {
+    let state = storage::get_state(env);
+    let end = state.releases.min(from.saturating_add(limit.min(MAX_PAGE)));
+    let mut page = Vec::new(env);
+    for index in from..end {
+        page.push_back(storage::get_release(env, index));
+    }
+
+    page
+}
+
+// Emission as of `at` (clock units): the curve bounded by the
+// surrounding checkpoints. The binary search reads checkpoints across
+// the history: callers keep them live with bump_history.
+pub fn emitted_at(env: &Env, at: u64) -> Result<i128, Error> {
+    let state = storage::get_state(env);
+    let (floor, ceiling, paused, _) = bracket(env, &state, at);
+    Ok(curve_delta(&state, at, paused, 0)?.clamp(floor, ceiling))
+}
+
+// Emission over [at, at + 1] in clock units, under the pauses in
+// effect at `at`.
+pub fn rate_at(env: &Env, at: u64) -> Result<i128, Error> {
+    let state = storage::get_state(env);
+    let (_, _, paused, halted) = bracket(env, &state, at);
+    let next = if halted { paused.saturating_add(1) } else { paused };
+    let emitted = curve_delta(&state, at, paused, 0)?;
+    curve_delta(&state, at.saturating_add(1), next, emitted)
+}
+
+// What a release `ahead` clock units from now would pay; nothing while
+// paused, as release.
+pub fn forecast(env: &Env, ahead: u64) -> Result<i128, Error> {
+    let state = storage::get_state(env);
+    if state.paused_at.is_some() {
+        return Ok(0);
+    }
+
+    payable(&state, now(env, state.clock).saturating_add(ahead))
+}
+
+pub fn set_cliff(env: &Env, duration: u64, catch_up: bool) -> Result<(), Error> {
+    let admin = storage::get_admin(env);
+    admin.require_auth();
+
+    let mut state = storage::get_state(env);
+    if now(env, state.clock) > state.origin {
+        return Err(Error::AlreadyStarted);
+    }
+
+    if duration > EmissionParams::of(state.clock).origin_horizon {
+        return Err(Error::InvalidCliff);
+    }
+
+    state.cliff = Cliff { duration, catch_up };
+    storage::set_state(env, &state);
+    storage::extend_ttl(env, state.ttl_threshold);
+
+    Ok(())
+}
+
+pub fn set_release_cap(env: &Env, cap: Option<ReleaseCap>) -> Result<(), Error> {
+    let admin = storage::get_admin(env);
+    admin.require_auth();
+
+    match cap {
+        Some(ReleaseCap::Amount(amount)) if amount <= 0 => return Err(Error::InvalidCap),
+        Some(ReleaseCap::Duration(0)) => return Err(Error::InvalidCap),
+        _ => {}
+    }
+
+    let mut state = storage::get_state(env);
+    state.cap = cap.unwrap_or(ReleaseCap::NONE);
+    storage::set_state(env, &state);
+    storage::extend_ttl(env, state.ttl_threshold);
+
+    Ok(())
+}
+
+pub fn set_release_threshold(env: &Env,
+    threshold: Option<ReleaseThreshold>) -> Result<(), Error> {
+    let admin = storage::get_admin(env);
+    admin.require_auth();
+
+    if threshold.is_some_and(|threshold| threshold.min_delta < 0) {
+        return Err(Error::InvalidThreshold);
+    }
+
+    let mut state = storage::get_state(env);
+    state.threshold = threshold.unwrap_or(ReleaseThreshold::NONE);
+    storage::set_state(env, &state);
+    storage::extend_ttl(env, state.ttl_threshold);
+
+    Ok(())
+}
+
+pub fn pause(env: &Env) -> Result<(), Error> {
+    let admin = storage::get_admin(env);
+    admin.require_auth();
+
+    let mut state = storage::get_state(env);
+    if state.paused_at.is_some() {
+        return Err(Error::AlreadyPaused);
+    }
+
+    let now = now(env, state.clock);
+    state.paused_at = Some(now);
+    checkpoint(env, &mut state, now);
+    storage::set_state(env, &state);
+    storage::extend_ttl(env, state.ttl_threshold);
+
+    Ok(())
+}
+
+pub fn unpause(env: &Env) -> Result<(), Error> {
+    let admin = storage::get_admin(env);
+    admin.require_auth();
+
+    let mut state = storage::get_state(env);
+    let Some(since) = state.paused_at else {
+        return Err(Error::NotPaused);
+    };
+
+    // Shift the curve by the paused duration.
+    let now = now(env, state.clock);
+    state.paused = state.paused.saturating_add(now.saturating_sub(since));
+    state.paused_at = None;
+    checkpoint(env, &mut state, now);
+    storage::set_state(env, &state);
+    storage::extend_ttl(env, state.ttl_threshold);
+
+    Ok(())
+}
+
+pub fn is_paused(env: &Env) -> bool {
+    storage::get_state(env).paused_at.is_some()
+}
+
+pub fn bump(env: &Env) {
+    storage::extend_ttl(env, storage::get_state(env).ttl_threshold);
+}
+
+// Extend primary release and checkpoint entries in [from, from + limit),
+// which releases and emitted_at read. Permissionless, like bump.
+pub fn bump_history(env: &Env, from: u32, limit: u32) {
+    let state = storage::get_state(env);
+    let end = from.saturating_add(limit.min(MAX_BUMP));
+    for index in from..state.releases.min(end) {
+        storage::extend_release(env, index, state.ttl_threshold);
+    }
+
+    for index in from..state.checkpoints.min(end) {
+        storage::extend_checkpoint(env, index, state.ttl_threshold);
+    }
+
+    storage::extend_ttl(env, state.ttl_threshold);
+}
+
+// Extend a tranche's release entries in [from, from + limit).
+pub fn bump_tranche_history(env: &Env, id: u32, from: u32, limit: u32) -> Result<(), Error> {
+    let state = storage::get_state(env);
+    let tranche = storage::get_tranche(env, id).ok_or(Error::TrancheNotFound)?;
+    let end = tranche.releases.min(from.saturating_add(limit.min(MAX_BUMP)));
+    for index in from..end {
+        storage::extend_tranche_release(env, id, index, state.ttl_threshold);
+    }
+
+    storage::extend_ttl(env, state.ttl_threshold);
+
+    Ok(())
+}
+
+// Escrow-token balance above what the curve has yet to release.
+pub fn surplus(env: &Env) -> Result<i128, Error> {
+    let state = storage::get_state(env);
+    surplus_of(env, &state)
+}
+
+pub fn set_surplus_policy(env: &Env, policy: SurplusPolicy) -> Result<(), Error> {
+    let admin = storage::get_admin(env);
+    admin.require_auth();
+
+    let mut state = storage::get_state(env);
+    state.surplus = policy;
+    storage::set_state(env, &state);
+    storage::extend_ttl(env, state.ttl_threshold);
+
+    Ok(())
+}
+
+// Burn `bps` of each release, out of the rewards share. Admin only;
+// zero disables the burn.
+pub fn set_burn_bps(env: &Env, bps: i128) -> Result<(), Error> {
+    let admin = storage::get_admin(env);
+    admin.require_auth();
+
+    let mut state = storage::get_state(env);
+    let params = EmissionParams::of(state.clock);
+    if !(0..=10000 - params.fund_bps).contains(&bps) {
+        return Err(Error::InvalidBurn);
+    }
+
+    state.burn_bps = bps;
+    storage::set_state(env, &state);
+    storage::extend_ttl(env, state.ttl_threshold);
+
+    Ok(())
+}
+
+pub fn burn_bps(env: &Env) -> i128 {
+    storage::get_state(env).burn_bps
+}
+
+pub fn burned(env: &Env) -> i128 {
+    storage::get_state(env).burned
+}
+
+// Apply the surplus policy to the current surplus. Permissionless: the
+// policy and its destinations are fixed by admin.
+pub fn reconcile(env: &Env) -> Result<i128, Error> {
+    let mut state = storage::get_state(env);
+    let amount = surplus_of(env, &state)?;
+    if amount == 0 {
+        return Ok(0);
+    }
+
+    let client = token::Client::new(env, &state.token);
+    let self_addr = env.current_contract_address();
+    match state.surplus {
+        SurplusPolicy::Fund => client.transfer(&self_addr, &state.fund, &amount),
+        SurplusPolicy::Rewards => client.transfer(&self_addr, &state.rewards, &amount),
+        // Top-ups below the minimum wait until the surplus accumulates.
+        SurplusPolicy::TopUp if amount < EmissionParams::of(state.clock).min_top_up => return Ok(0),
+        SurplusPolicy::TopUp => {
+            let now = now(env, state.clock);
+            grow(&mut state, now, amount)?;
+            checkpoint(env, &mut state, now);
+            storage::set_state(env, &state);
+        }
+    }
+
+    storage::extend_ttl(env, state.ttl_threshold);
+
+    Ok(amount)
+}
+
+// Pull `amount` from `from` into the pool, rebasing the curve so that
+// emitted-to-date is unchanged and the amount joins the remaining tail.
+// At least min_top_up, so that a rebase is never spammed for dust.
+pub fn top_up(env: &Env, from: Address, amount: i128) -> Result<(), Error> {
+    from.require_auth();
+    let mut state = storage::get_state(env);
+    if amount < EmissionParams::of(state.clock).min_top_up {
+        return Err(Error::InvalidAmount);
+    }
+
+    let now = now(env, state.clock);
+    grow(&mut state, now, amount)?;
+    let self_addr = env.current_contract_address();
+    token::Client::new(env, &state.token).transfer(&from, &self_addr, &amount);
+    checkpoint(env, &mut state, now);
+    storage::set_state(env, &state);
+    storage::extend_ttl(env, state.ttl_threshold);
+
+    Ok(())
+}
+
+// Change the half-life at the current clock without a jump: emitted
+// to date is unchanged and only the future slope moves. Each change
+// is bounded to half_life_step_bps of the current value, at most once
+// per retune_period.
+pub fn set_half_life(env: &Env, half_life: i128) -> Result<(), Error> {
+    let admin = storage::get_admin(env);
+    admin.require_auth();
+
+    if half_life <= 0 || half_life > emission::MAX_HALF_LIFE {
+        return Err(Error::InvalidHalfLife);
+    }
+
+    let mut state = storage::get_state(env);
+    let params = EmissionParams::of(state.clock);
+    let step = state.half_life.checked_mul(params.half_life_step_bps)
+        .ok_or(Error::ArithmeticOverflow)? / 10000;
+    if half_life < state.half_life - step || half_life > state.half_life + step {
+        return Err(Error::InvalidHalfLife);
+    }
+
+    let now = now(env, state.clock);
+    let unlocked = state.retuned_at
+        .is_none_or(|at| now >= at.saturating_add(params.retune_period));
+    if !unlocked {
+        return Err(Error::HalfLifeLocked);
+    }
+
+    let pool = state.pool;
+    rebase(&mut state, now, pool, half_life)?;
+    state.retuned_at = Some(now);
+    checkpoint(env, &mut state, now);
+    storage::set_state(env, &state);
+    storage::extend_ttl(env, state.ttl_threshold);
+
+    Ok(())
+}
+
+pub fn half_life(env: &Env) -> i128 {
+    storage::get_state(env).half_life
+}
+
+// Forward the balance of a token above what its tranches still owe to
+// the fund: all of it for a foreign token. Permissionless: the
+// destination is fixed. The escrowed token goes through reconcile.
+pub fn sweep(env: &Env, token: Address) -> Result<i128, Error> {
+    let state = storage::get_state(env);
+    if token == state.token {
+        return Err(Error::InvalidToken);
+    }
+
+    let client = token::Client::new(env, &token);
+    let self_addr = env.current_contract_address();
+    let amount = client.balance(&self_addr).checked_sub(locked(env, &state, &token)?)
+        .ok_or(Error::ArithmeticOverflow)?.max(0);
+    if amount > 0 {
+        client.transfer(&self_addr, &state.fund, &amount);
+    }
+
+    storage::extend_ttl(env, state.ttl_threshold);
+
+    Ok(amount)
+}
+
+pub fn set_ttl_threshold(env: &Env, threshold: u32) -> Result<(), Error> {
+    let admin = storage::get_admin(env);
+    admin.require_auth();
+
+    if threshold > env.storage().max_ttl() {
+        return Err(Error::InvalidTtl);
+    }
+
+    let mut state = storage::get_state(env);
+    state.ttl_threshold = threshold;
+    storage::set_state(env, &state);
+    storage::extend_ttl(env, threshold);
+
+    Ok(())
 }
 
 pub fn upgrade(env: &Env, hash: BytesN<32>) -> Result<(), Error> {
@@ -80,7 +696,273 @@ pub fn upgrade(env: &Env, hash: BytesN<32>) -> Result<(), Error> {
     admin.require_auth();
 
     env.deployer().update_current_contract_wasm(hash);
-    storage::extend_ttl(env);
+    storage::extend_ttl(env, storage::get_state(env).ttl_threshold);
+
+    Ok(())
+}
+
+pub fn migrate(env: &Env) -> Result<(), Error> {
+    let admin = storage::get_admin(env);
+    admin.require_auth();
+
+    if storage::get_version(env) >= storage::VERSION {
+        return Err(Error::AlreadyMigrated);
+    }
+
+    // 1.0.0 kept each field under its own key, on the ledger clock with
+    // no release history; a checkpoint anchors the emission released
+    // before history was recorded.
+    let origin = u64::from(legacy::<u32>(env, Storage::Ledger));
+    let mut state = initial(legacy(env, Storage::Token), legacy(env, Storage::Fund),
+        legacy(env, Storage::Rewards), Clock::Ledger, origin);
+    state.emitted = legacy(env, Storage::Emitted);
+    if state.emitted > 0 {
+        checkpoint(env, &mut state, now(env, Clock::Ledger));
+    }
+
+    storage::set_state(env, &state);
+    storage::set_version(env, storage::VERSION);
+    storage::extend_ttl(env, state.ttl_threshold);
+
+    Ok(())
+}
+
+pub fn version(env: &Env) -> u32 {
+    storage::get_version(env)
+}
+
+fn legacy<V: TryFromVal<Env, Val>>(env: &Env, key: Storage) -> V {
+    storage::take_legacy(env, key).unwrap_or_else(|| panic!("legacy key not set"))
+}
+
+// Curve Δ at `at`, honoring cliff and pauses.
+fn delta(state: &State, at: u64, realized: i128) -> Result<i128, Error> {
+    curve_delta(state, at, paused(state, at), realized)
+}
+
+// The carry adds back what the last rebase rounded away, within the pool.
+fn curve_delta(state: &State, at: u64, paused: u64,
+    realized: i128) -> Result<i128, Error> {
+    let Some(elapsed) = elapsed(state, at, paused)? else {
+        return Ok(0);
+    };
+
+    let emitted = emission::delta(state.pool, state.half_life, 0, elapsed, 0, 0)?
+        .checked_add(state.carry)
+        .ok_or(Error::ArithmeticOverflow)?
+        .min(state.pool);
+    Ok(emitted.checked_sub(realized).ok_or(Error::ArithmeticOverflow)?.max(0))
+}
+
+// Position on the curve at `at`: time since the curve origin net of
+// pauses, plus the rebase shift. None before the cliff.
+fn elapsed(state: &State, at: u64, paused: u64) -> Result<Option<u64>, Error> {
+    let start = state.origin.saturating_add(state.cliff.duration);
+    if at < start {
+        return Ok(None);
+    }
+
+    let origin = if state.cliff.catch_up { state.origin } else { start };
+    let elapsed = ((at - origin).saturating_sub(paused) as i128).checked_add(state.shift)
+        .ok_or(Error::ArithmeticOverflow)?;
+    Ok(Some(u64::try_from(elapsed.max(0)).unwrap_or(u64::MAX)))
+}
+
+// Amount a release at `at` pays: Δ gated by the threshold, then capped.
+fn payable(state: &State, at: u64) -> Result<i128, Error> {
+    let amount = delta(state, at, state.emitted)?;
+    let gap = state.last_release
+        .map_or(u64::MAX, |last| at.saturating_sub(last));
+    if amount < state.threshold.min_delta || gap < state.threshold.min_gap {
+        return Ok(0);
+    }
+
+    capped(state, at, amount)
+}
+
+// Clamp Δ to the release cap; the remainder accrues to later calls.
+fn capped(state: &State, at: u64, amount: i128) -> Result<i128, Error> {
+    let cap = match state.cap {
+        ReleaseCap::Amount(amount) => amount,
+        ReleaseCap::Duration(duration) => {
+            let emitted = delta(state, at.saturating_sub(duration), 0)?;
+            delta(state, at, emitted)?
+        }
+    };
+
+    Ok(amount.min(cap))
+}
+
+fn surplus_of(env: &Env, state: &State) -> Result<i128, Error> {
+    let balance = token::Client::new(env, &state.token)
+        .balance(&env.current_contract_address());
+    let surplus = balance.checked_sub(locked(env, state, &state.token)?)
+        .ok_or(Error::ArithmeticOverflow)?;
+    Ok(surplus.max(0))
+}
+
+// Balance of `token` still owed by the primary curve and the tranches.
+fn locked(env: &Env, state: &State, token: &Address) -> Result<i128, Error> {
+    let mut locked = if *token == state.token {
+        state.pool.checked_sub(state.emitted).ok_or(Error::ArithmeticOverflow)?
+    } else {
+        0
+    };
+
+    for id in storage::get_tranches(env).iter() {
+        match storage::get_tranche(env, id) {
+            Some(tranche) if tranche.token == *token => {
+                locked = tranche.pool.checked_sub(tranche.emitted)
+                    .and_then(|owed| locked.checked_add(owed))
+                    .ok_or(Error::ArithmeticOverflow)?;
+            }
+            _ => {}
+        }
+    }
+
+    Ok(locked)
+}
+
+// Pay a tranche its Δ, split like the primary curve, and record it in
+// the tranche history. Only tranches of the escrow token burn, into
+// `burned`; other tokens are outside the burn policy and pay the full
+// rewards share. Pauses halt tranches too; only pauses after the
+// tranche was added shift it.
+fn pay_tranche(env: &Env, state: &mut State, id: u32, tranche: &mut Tranche) -> Result<i128, Error> {
+    if state.paused_at.is_some() {
+        return Ok(0);
+    }
+
+    let now = now(env, state.clock);
+    let paused = paused(state, now).saturating_sub(tranche.paused);
+    let delta = emission::delta(tranche.pool, tranche.half_life, tranche.origin,
+        now, paused, tranche.emitted)?;
+    if delta == 0 {
+        return Ok(0);
+    }
+
+    let params = EmissionParams::of(state.clock);
+    let fund = delta.checked_mul(params.fund_bps)
+        .ok_or(Error::ArithmeticOverflow)? / 10000;
+    let burn = if tranche.token == state.token {
+        delta.checked_mul(state.burn_bps).ok_or(Error::ArithmeticOverflow)? / 10000
+    } else {
+        0
+    };
+    let rewards = delta - fund - burn;
+    let client = token::Client::new(env, &tranche.token);
+    let self_addr = env.current_contract_address();
+    client.transfer(&self_addr, &tranche.fund, &fund);
+    client.transfer(&self_addr, &tranche.rewards, &rewards);
+    if burn > 0 {
+        client.burn(&self_addr, &burn);
+    }
+
+    tranche.emitted = tranche.emitted.checked_add(delta)
+        .ok_or(Error::ArithmeticOverflow)?;
+    state.burned = state.burned.checked_add(burn)
+        .ok_or(Error::ArithmeticOverflow)?;
+    storage::set_tranche_release(env, id, tranche.releases, &Release {
+        ledger: env.ledger().sequence(),
+        timestamp: env.ledger().timestamp(),
+        delta,
+        fund,
+        rewards,
+        burn,
+        cumulative: tranche.emitted,
+    });
+    tranche.releases += 1;
+
+    TrancheReleased { id, token: tranche.token.clone(), delta, fund, rewards, burn }.publish(env);
+
+    Ok(delta)
+}
+
+// Grow the pool by `amount` at `at` without a jump in the curve.
+fn grow(state: &mut State, at: u64, amount: i128) -> Result<(), Error> {
+    let pool = state.pool.checked_add(amount)
+        .filter(|pool| *pool <= emission::MAX_POOL)
+        .ok_or(Error::ArithmeticOverflow)?;
+
+    let half_life = state.half_life;
+    rebase(state, at, pool, half_life)
+}
+
+// Move the curve onto `pool` and `half_life` at `at`, keeping emission
+// to date exactly: the elapsed time is shifted to where the new curve
+// has emitted what the old one had, rounded down (emission::elapsed),
+// and the carry holds the remainder. Emission before a rebase is only
+// bounded by checkpoints afterwards.
+fn rebase(state: &mut State, at: u64, pool: i128, half_life: i128) -> Result<(), Error> {
+    // Before the cliff nothing has been emitted: only the curve changes.
+    let emitted = delta(state, at, 0)?;
+    if let (true, Some(current)) = (emitted > 0, elapsed(state, at, paused(state, at))?) {
+        let target = emission::elapsed(pool, half_life, emitted)?;
+        state.shift = state.shift.checked_add(target as i128 - current as i128)
+            .ok_or(Error::ArithmeticOverflow)?;
+        state.carry = emitted - emission::delta(pool, half_life, 0, target, 0, 0)?;
+    }
+
+    state.pool = pool;
+    state.half_life = half_life;
 
     Ok(())
-}
\ No newline at end of file
+}
+
+// Checkpoints around `at`: emission floor and ceiling, the pause total
+// in effect at `at` and whether a pause was ongoing then. Pause state
+// is constant between checkpoints.
+fn bracket(env: &Env, state: &State, at: u64) -> (i128, i128, u64, bool) {
+    let count = state.checkpoints;
+    let (mut low, mut high) = (0, count);
+    while low < high {
+        let mid = low + (high - low) / 2;
+        if storage::get_checkpoint(env, mid).at <= at {
+            low = mid + 1;
+        } else {
+            high = mid;
+        }
+    }
+
+    let (floor, paused, halted) = match low {
+        0 => (0, 0, false),
+        _ => {
+            let checkpoint = storage::get_checkpoint(env, low - 1);
+            let halted = if checkpoint.halted { at - checkpoint.at } else { 0 };
+            (checkpoint.emitted, checkpoint.paused.saturating_add(halted), checkpoint.halted)
+        }
+    };
+
+    let ceiling = if low < count {
+        storage::get_checkpoint(env, low).emitted
+    } else {
+        i128::MAX
+    };
+
+    (floor, ceiling, paused, halted)
+}
+
+fn checkpoint(env: &Env, state: &mut State, at: u64) {
+    storage::set_checkpoint(env, state.checkpoints, &Checkpoint {
+        at,
+        paused: state.paused,
+        halted: state.paused_at.is_some(),
+        emitted: state.emitted,
+    });
+    state.checkpoints += 1;
+}
+
+// Paused duration up to `at`, ongoing pause included.
+fn paused(state: &State, at: u64) -> u64 {
+    match state.paused_at {
+        Some(since) => state.paused.saturating_add(at.saturating_sub(since)),
+        None => state.paused,
+    }
+}
+
+fn now(env: &Env, clock: Clock) -> u64 {
+    match clock {
+        Clock::Ledger => env.ledger().sequence() as u64,
+        Clock::Timestamp => env.ledger().timestamp(),
+    }
+}
@@ -13,8 +13,10 @@ mod storage;
 mod emission;
 mod types;
 
-use soroban_sdk::{contract, contractimpl, Address, BytesN, Env};
-use types::Error;
+use soroban_sdk::{contract, contractimpl, Address, BytesN, Env, Map, Vec};
+use types::{Error, Release, ReleaseCap, ReleaseThreshold, Tranche};
+
+pub use types::{Clock, EmissionParams, SurplusPolicy};
 
 #[contract]
 pub struct Escrow;
@@ -26,14 +28,168 @@ impl Escrow {
         escrow::initialize(&env, admin, token, fund, rewards, ledger)
     }
 
-    pub fn release(env: Env) -> i128 {
+    pub fn initialize_timestamp(env: Env, admin: Address, token: Address,
+        fund: Address, rewards: Address, timestamp: u64) -> Result<(), Error> {
+        escrow::initialize_timestamp(&env, admin, token, fund, rewards, timestamp)
+    }
+
+    pub fn release(env: Env) -> Result<i128, Error> {
         escrow::release(&env)
     }
 
+    #[allow(clippy::too_many_arguments)]
+    pub fn add_tranche(env: Env, id: u32, token: Address, fund: Address,
+        rewards: Address, pool: i128, origin: u64, half_life: i128) -> Result<(), Error> {
+        escrow::add_tranche(&env, id, token, fund, rewards, pool, origin, half_life)
+    }
+
+    pub fn release_tranche(env: Env, id: u32) -> Result<i128, Error> {
+        escrow::release_tranche(&env, id)
+    }
+
+    pub fn release_all(env: Env) -> Result<Map<Address, i128>, Error> {
+        escrow::release_all(&env)
+    }
+
+    pub fn release_token(env: Env, token: Address) -> Result<i128, Error> {
+        escrow::release_token(&env, token)
+    }
+
+    pub fn locked(env: Env, token: Address) -> Result<i128, Error> {
+        escrow::locked_of(&env, token)
+    }
+
+    pub fn tranche(env: Env, id: u32) -> Result<Tranche, Error> {
+        escrow::tranche(&env, id)
+    }
+
+    pub fn tranches(env: Env) -> Vec<u32> {
+        escrow::tranches(&env)
+    }
+
+    pub fn tranche_releases(env: Env, id: u32, from: u32, limit: u32) -> Result<Vec<Release>, Error> {
+        escrow::tranche_releases(&env, id, from, limit)
+    }
+
+    pub fn release_count(env: Env) -> u32 {
+        escrow::release_count(&env)
+    }
+
+    pub fn releases(env: Env, from: u32, limit: u32) -> Vec<Release> {
+        escrow::releases(&env, from, limit)
+    }
+
+    pub fn emitted_at(env: Env, at: u64) -> Result<i128, Error> {
+        escrow::emitted_at(&env, at)
+    }
+
+    pub fn rate_at(env: Env, at: u64) -> Result<i128, Error> {
+        escrow::rate_at(&env, at)
+    }
+
+    pub fn forecast(env: Env, ahead: u64) -> Result<i128, Error> {
+        escrow::forecast(&env, ahead)
+    }
+
+    pub fn set_cliff(env: Env, duration: u64, catch_up: bool) -> Result<(), Error> {
+        escrow::set_cliff(&env, duration, catch_up)
+    }
+
+    pub fn set_release_cap(env: Env, cap: Option<ReleaseCap>) -> Result<(), Error> {
+        escrow::set_release_cap(&env, cap)
+    }
+
+    pub fn set_release_threshold(env: Env,
+        threshold: Option<ReleaseThreshold>) -> Result<(), Error> {
+        escrow::set_release_threshold(&env, threshold)
+    }
+
+    pub fn pause(env: Env) -> Result<(), Error> {
+        escrow::pause(&env)
+    }
+
+    pub fn unpause(env: Env) -> Result<(), Error> {
+        escrow::unpause(&env)
+    }
+
+    pub fn is_paused(env: Env) -> bool {
+        escrow::is_paused(&env)
+    }
+
+    pub fn bump(env: Env) {
+        escrow::bump(&env)
+    }
+
+    pub fn bump_history(env: Env, from: u32, limit: u32) {
+        escrow::bump_history(&env, from, limit)
+    }
+
+    pub fn bump_tranche_history(env: Env, id: u32, from: u32, limit: u32) -> Result<(), Error> {
+        escrow::bump_tranche_history(&env, id, from, limit)
+    }
+
+    pub fn surplus(env: Env) -> Result<i128, Error> {
+        escrow::surplus(&env)
+    }
+
+    pub fn set_surplus_policy(env: Env, policy: SurplusPolicy) -> Result<(), Error> {
+        escrow::set_surplus_policy(&env, policy)
+    }
+
+    pub fn set_burn_bps(env: Env, bps: i128) -> Result<(), Error> {
+        escrow::set_burn_bps(&env, bps)
+    }
+
+    pub fn burn_bps(env: Env) -> i128 {
+        escrow::burn_bps(&env)
+    }
+
+    pub fn burned(env: Env) -> i128 {
+        escrow::burned(&env)
+    }
+
+    pub fn reconcile(env: Env) -> Result<i128, Error> {
+        escrow::reconcile(&env)
+    }
+
+    pub fn top_up(env: Env, from: Address, amount: i128) -> Result<(), Error> {
+        escrow::top_up(&env, from, amount)
+    }
+
+    pub fn set_half_life(env: Env, half_life: i128) -> Result<(), Error> {
+        escrow::set_half_life(&env, half_life)
+    }
+
+    pub fn half_life(env: Env) -> i128 {
+        escrow::half_life(&env)
+    }
+
+    pub fn sweep(env: Env, token: Address) -> Result<i128, Error> {
+        escrow::sweep(&env, token)
+    }
+
+    pub fn set_ttl_threshold(env: Env, threshold: u32) -> Result<(), Error> {
+        escrow::set_ttl_threshold(&env, threshold)
+    }
+
     pub fn upgrade(env: Env, hash: BytesN<32>) -> Result<(), Error> {
         escrow::upgrade(&env, hash)
     }
+
+    pub fn migrate(env: Env) -> Result<(), Error> {
+        escrow::migrate(&env)
+    }
+
+    pub fn version(env: Env) -> u32 {
+        escrow::version(&env)
+    }
 }
 
 #[cfg(test)]
-mod test;
\ No newline at end of file
+mod test;
+
+#[cfg(test)]
+mod test_budget;
+
+#[cfg(test)]
+mod test_props;
\ No newline at end of file
@@ -6,8 +6,19 @@
     Author: Fred Kyung-jin Rezeau (오경진 吳景振) <hello@kyungj.in>
 */
 
-use crate::types::Storage;
-use soroban_sdk::{Address, Env};
+use crate::types::{Checkpoint, Release, State, Storage, Tranche};
+use soroban_sdk::{Address, Env, TryFromVal, Val, Vec};
+
+// Storage layout version; 1.0.0 instances predate the key.
+pub const VERSION: u32 = 2;
+
+// Default ttl_threshold, about a week of ledgers; admin can adjust it
+// with set_ttl_threshold. Applies to the instance and history entries.
+pub const TTL_THRESHOLD: u32 = 120_960;
+
+pub fn has_admin(env: &Env) -> bool {
+    env.storage().instance().has(&Storage::Admin)
+}
 
 pub fn get_admin(env: &Env) -> Address {
     env.storage().instance()
@@ -20,67 +31,120 @@ pub fn set_admin(env: &Env, admin: &Address) {
         .set::<Storage, Address>(&Storage::Admin, admin);
 }
 
-pub fn has_token(env: &Env) -> bool {
-    env.storage().instance().has(&Storage::Token)
+pub fn get_state(env: &Env) -> State {
+    env.storage().instance()
+        .get::<Storage, State>(&Storage::State)
+        .unwrap_or_else(|| panic!("state not set"))
 }
 
-pub fn get_token(env: &Env) -> Address {
+pub fn set_state(env: &Env, state: &State) {
     env.storage().instance()
-        .get::<Storage, Address>(&Storage::Token)
-        .unwrap_or_else(|| panic!("token not set"))
+        .set::<Storage, State>(&Storage::State, state);
 }
 
-pub fn set_token(env: &Env, token: &Address) {
-    env.storage().instance()
-        .set::<Storage, Address>(&Storage::Token, token);
+pub fn get_release(env: &Env, index: u32) -> Release {
+    env.storage().persistent()
+        .get::<Storage, Release>(&Storage::Release(index))
+        .unwrap_or_else(|| panic!("release not set"))
 }
 
-pub fn get_fund(env: &Env) -> Address {
-    env.storage().instance()
-        .get::<Storage, Address>(&Storage::Fund)
-        .unwrap_or_else(|| panic!("fund not set"))
+pub fn set_release(env: &Env, index: u32, release: &Release) {
+    let key = Storage::Release(index);
+    env.storage().persistent()
+        .set::<Storage, Release>(&key, release);
+    let max_ttl = env.storage().max_ttl();
+    env.storage().persistent().extend_ttl(&key, max_ttl, max_ttl);
 }
 
-pub fn set_fund(env: &Env, fund: &Address) {
-    env.storage().instance()
-        .set::<Storage, Address>(&Storage::Fund, fund);
+pub fn get_checkpoint(env: &Env, index: u32) -> Checkpoint {
+    env.storage().persistent()
+        .get::<Storage, Checkpoint>(&Storage::Checkpoint(index))
+        .unwrap_or_else(|| panic!("checkpoint not set"))
+}
+
+pub fn set_checkpoint(env: &Env, index: u32, checkpoint: &Checkpoint) {
+    let key = Storage::Checkpoint(index);
+    env.storage().persistent()
+        .set::<Storage, Checkpoint>(&key, checkpoint);
+    let max_ttl = env.storage().max_ttl();
+    env.storage().persistent().extend_ttl(&key, max_ttl, max_ttl);
 }
 
-pub fn get_rewards(env: &Env) -> Address {
+pub fn get_tranches(env: &Env) -> Vec<u32> {
     env.storage().instance()
-        .get::<Storage, Address>(&Storage::Rewards)
-        .unwrap_or_else(|| panic!("rewards not set"))
+        .get::<Storage, Vec<u32>>(&Storage::Tranches)
+        .unwrap_or_else(|| Vec::new(env))
 }
 
-pub fn set_rewards(env: &Env, rewards: &Address) {
+pub fn set_tranches(env: &Env, ids: &Vec<u32>) {
     env.storage().instance()
-        .set::<Storage, Address>(&Storage::Rewards, rewards);
+        .set::<Storage, Vec<u32>>(&Storage::Tranches, ids);
 }
 
-pub fn get_ledger(env: &Env) -> u32 {
+pub fn get_tranche(env: &Env, id: u32) -> Option<Tranche> {
     env.storage().instance()
-        .get::<Storage, u32>(&Storage::Ledger)
-        .unwrap_or_else(|| panic!("ledger not set"))
+        .get::<Storage, Tranche>(&Storage::Tranche(id))
 }
 
-pub fn set_ledger(env: &Env, start: u32) {
+pub fn set_tranche(env: &Env, id: u32, tranche: &Tranche) {
     env.storage().instance()
-        .set::<Storage, u32>(&Storage::Ledger, &start);
+        .set::<Storage, Tranche>(&Storage::Tranche(id), tranche);
 }
 
-pub fn get_emitted(env: &Env) -> i128 {
+pub fn get_tranche_release(env: &Env, id: u32, index: u32) -> Release {
+    env.storage().persistent()
+        .get::<Storage, Release>(&Storage::TrancheRelease(id, index))
+        .unwrap_or_else(|| panic!("tranche release not set"))
+}
+
+pub fn set_tranche_release(env: &Env, id: u32, index: u32, release: &Release) {
+    let key = Storage::TrancheRelease(id, index);
+    env.storage().persistent()
+        .set::<Storage, Release>(&key, release);
+    let max_ttl = env.storage().max_ttl();
+    env.storage().persistent().extend_ttl(&key, max_ttl, max_ttl);
+}
+
+pub fn get_version(env: &Env) -> u32 {
     env.storage().instance()
-        .get::<Storage, i128>(&Storage::Emitted)
-        .unwrap_or_else(|| panic!("emitted not set"))
+        .get::<Storage, u32>(&Storage::Version)
+        .unwrap_or(1)
 }
 
-pub fn set_emitted(env: &Env, emitted: i128) {
+pub fn set_version(env: &Env, version: u32) {
     env.storage().instance()
-        .set::<Storage, i128>(&Storage::Emitted, &emitted);
+        .set::<Storage, u32>(&Storage::Version, &version);
+}
+
+// Reads and removes a key of the 1.0.0 layout.
+pub fn take_legacy<V: TryFromVal<Env, Val>>(env: &Env, key: Storage) -> Option<V> {
+    let value = env.storage().instance().get::<Storage, V>(&key);
+    env.storage().instance().remove(&key);
+    value
 }
 
-pub fn extend_ttl(env: &Env) {
+pub fn extend_ttl(env: &Env, ttl_threshold: u32) {
     let max_ttl = env.storage().max_ttl();
-    let threshold = max_ttl.saturating_sub(120_960);
+    let threshold = max_ttl.saturating_sub(ttl_threshold);
     env.storage().instance().extend_ttl(threshold, max_ttl);
-}
\ No newline at end of file
+}
+
+// History entries are only extended on write; these follow the
+// instance threshold when bumped.
+pub fn extend_release(env: &Env, index: u32, ttl_threshold: u32) {
+    extend_persistent(env, &Storage::Release(index), ttl_threshold);
+}
+
+pub fn extend_checkpoint(env: &Env, index: u32, ttl_threshold: u32) {
+    extend_persistent(env, &Storage::Checkpoint(index), ttl_threshold);
+}
+
+pub fn extend_tranche_release(env: &Env, id: u32, index: u32, ttl_threshold: u32) {
+    extend_persistent(env, &Storage::TrancheRelease(id, index), ttl_threshold);
+}
+
+fn extend_persistent(env: &Env, key: &Storage, ttl_threshold: u32) {
+    let max_ttl = env.storage().max_ttl();
+    let threshold = max_ttl.saturating_sub(ttl_threshold);
+    env.storage().persistent().extend_ttl(key, threshold, max_ttl);
+}
@@ -8,47 +8,72 @@
 
 use crate::*;
 use crate::emission;
-use crate::types::EmissionParams;
-use soroban_sdk::{testutils::{Address as _, Ledger}, token, Address, Bytes, Env};
+use crate::storage;
+use crate::types::{Clock, EmissionParams, Error, ReleaseCap, ReleaseThreshold, State, Storage,
+    SurplusPolicy, Tranche, TrancheReleased};
+use soroban_sdk::{testutils::{storage::{Instance, Persistent}, Address as _, Events, Ledger, Register}, map, token, vec, Address,
+    Bytes, Env, Event, xdr::{LedgerKey, ScAddress, ScVal}};
 
-#[test]
-#[should_panic]
-fn test_escrow_provenance() {
+const POOL: i128 = EmissionParams::default().pool();
+pub(crate) const LEDGER: u32 = 1_000;
+
+pub(crate) struct Setup {
+    pub(crate) env: Env,
+    pub(crate) client: EscrowClient<'static>,
+    pub(crate) contract_id: Address,
+    pub(crate) token_admin: Address,
+    pub(crate) token_id: Address,
+    pub(crate) token: token::Client<'static>,
+    pub(crate) token_admin_client: token::StellarAssetClient<'static>,
+    pub(crate) admin: Address,
+    pub(crate) fund: Address,
+    pub(crate) rewards: Address,
+}
+
+// `contract` registered next to a fresh SAC, at ledger LEDGER with
+// admin holding the total supply; not initialized. Shared with the
+// budget and property tests.
+pub(crate) fn deploy(contract: impl Register) -> Setup {
     let params = EmissionParams::default();
     let env = Env::default();
-    let contract_id = env.register(Escrow, ());
+    let contract_id = env.register(contract, ());
     let client = EscrowClient::new(&env, &contract_id);
     let token_admin = Address::generate(&env);
     let token_id = env.register_stellar_asset_contract_v2(token_admin.clone()).address();
+    let token = token::Client::new(&env, &token_id);
     let token_admin_client = token::StellarAssetClient::new(&env, &token_id);
     let admin = Address::generate(&env);
     let fund = Address::generate(&env);
     let rewards = Address::generate(&env);
-    let ledger: u32 = 1_000;
 
     env.mock_all_auths();
-    env.ledger().set_sequence_number(ledger);
-    token_admin_client.mint(&admin, &(params.total_supply - 1));
-    client.initialize(&admin, &token_id, &fund, &rewards, &ledger);
+    env.ledger().set_sequence_number(LEDGER);
+    token_admin_client.mint(&admin, &params.total_supply);
+
+    Setup { env, client, contract_id, token_admin, token_id, token, token_admin_client, admin, fund, rewards }
+}
+
+// An escrow initialized on the ledger clock with its origin at LEDGER.
+pub(crate) fn setup() -> Setup {
+    let setup = deploy(Escrow);
+    setup.client.initialize(&setup.admin, &setup.token_id, &setup.fund, &setup.rewards, &LEDGER);
+    setup
 }
 
+#[test]
+#[should_panic]
+fn test_escrow_provenance() {
+    let Setup { token, client, admin, token_id, fund, rewards, .. } = deploy(Escrow);
+    token.burn(&admin, &1);
+    client.initialize(&admin, &token_id, &fund, &rewards, &LEDGER);
+}
 
 #[test]
 fn test_escrow_release() {
     let params = EmissionParams::default();
-    let env = Env::default();
-    let allocation = params.allocation;
-    let contract_id = env.register(Escrow, ());
-    let client = EscrowClient::new(&env, &contract_id);
-    let token_admin = Address::generate(&env);
-    let token_id = env.register_stellar_asset_contract_v2(token_admin.clone()).address();
-    let token = token::Client::new(&env, &token_id);
-    let token_admin_client = token::StellarAssetClient::new(&env, &token_id);
-    let admin = Address::generate(&env);
-    let fund = Address::generate(&env);
-    let rewards = Address::generate(&env);
-    let mut current_ledger = 1000u32;
-    let mut cumulative_fund = allocation;
+    let Setup { env, client, token, fund, rewards, .. } = setup();
+    let mut current_ledger = LEDGER;
+    let mut cumulative_fund = params.allocation;
     let mut cumulative_rewards = 0i128;
 
     const REF: [(u32, i128, i128, i128); 20] = [
@@ -74,13 +99,6 @@ fn test_escrow_release() {
         (16,          3551421597,           35514215,         3515907382)
     ];
 
-    env.mock_all_auths();
-
-    token_admin_client.mint(&admin, &params.total_supply);
-    env.ledger().set_sequence_number(current_ledger);
-
-    client.initialize(&admin, &token_id, &fund, &rewards, &current_ledger);
-
     for (increment, expected_emissions, expected_fund, expected_rewards) in REF.iter() {
         current_ledger += increment;
         env.ledger().set_sequence_number(current_ledger);
@@ -97,8 +115,118 @@ fn test_escrow_release() {
 
 #[test]
 fn test_emission_delta_clamp() {
-    assert_eq!(emission::delta(1000, 1000, 0), 0);
-    assert_eq!(emission::delta(1000,  999, 0), 0);
+    let half_life = EmissionParams::default().half_life;
+    assert_eq!(emission::delta(POOL, half_life, 1000, 1000, 0, 0).unwrap(), 0);
+    assert_eq!(emission::delta(POOL, half_life, 1000,  999, 0, 0).unwrap(), 0);
+}
+
+#[test]
+fn test_emission_overflow() {
+    use emission::{MAX_HALF_LIFE, MAX_POOL};
+
+    // Documented bounds hold at every extreme.
+    for &(pool, half_life) in &[(MAX_POOL, MAX_HALF_LIFE), (MAX_POOL, 1), (1, MAX_HALF_LIFE)] {
+        for &target in &[1, u32::MAX as u64, u64::MAX] {
+            let delta = emission::delta(pool, half_life, 0, target, 0, 0).unwrap();
+            assert!((0..=pool).contains(&delta));
+            assert_eq!(emission::delta(pool, half_life, 0, target, 0, delta).unwrap(), 0);
+            assert_eq!(emission::delta(pool, half_life, 0, target, u64::MAX, 0).unwrap(), 0);
+        }
+    }
+
+    // Outside the bounds, errors instead of trapping.
+    assert_eq!(emission::delta(i128::MAX, 2, 0, 1, 0, 0), Err(Error::ArithmeticOverflow));
+    assert_eq!(emission::delta(POOL, i128::MAX, 0, u64::MAX, 0, 0), Err(Error::ArithmeticOverflow));
+    assert_eq!(emission::delta(POOL, 0, 0, 1, 0, i128::MIN), Err(Error::ArithmeticOverflow));
+
+    // Views stay total at the far end of the clock.
+    let params = EmissionParams::default();
+    let Setup { client, .. } = setup();
+
+    let forecast = client.forecast(&u64::MAX);
+    assert!(forecast > 0 && forecast <= params.pool());
+    assert_eq!(client.emitted_at(&u64::MAX), forecast);
+    assert_eq!(client.rate_at(&u64::MAX), 0);
+}
+
+#[test]
+fn test_state_overflow() {
+    const ECOSYSTEM: i128 = 1_000_000_000_000_000;
+
+    let params = EmissionParams::default();
+    let Setup { env, client, contract_id, token_id, admin, fund, .. } = setup();
+    let committee = Address::generate(&env);
+    let origin = LEDGER as u64;
+    client.add_tranche(&1, &token_id, &fund, &committee, &ECOSYSTEM, &origin, &params.half_life);
+    env.ledger().set_sequence_number(LEDGER + 5_000);
+
+    // Stored values out of range error instead of wrapping or trapping.
+    let state = env.as_contract(&contract_id, || storage::get_state(&env));
+    let tranche = client.tranche(&1);
+    let set_state = |state: &State| env.as_contract(&contract_id, || storage::set_state(&env, state));
+    let set_tranche = |tranche: &Tranche| env.as_contract(&contract_id, || storage::set_tranche(&env, 1, tranche));
+
+    // Half-life step.
+    set_state(&State { half_life: i128::MAX, ..state.clone() });
+    assert_eq!(client.try_set_half_life(&params.half_life), Err(Ok(Error::ArithmeticOverflow)));
+
+    // Curve shift, read by every release and rebase.
+    set_state(&State { shift: i128::MAX, ..state.clone() });
+    assert_eq!(client.try_release(), Err(Ok(Error::ArithmeticOverflow)));
+    assert_eq!(client.try_top_up(&admin, &params.min_top_up), Err(Ok(Error::ArithmeticOverflow)));
+    set_state(&state);
+
+    // Tranche emission.
+    set_tranche(&Tranche { emitted: i128::MIN, ..tranche.clone() });
+    assert_eq!(client.try_release_tranche(&1), Err(Ok(Error::ArithmeticOverflow)));
+
+    // Locked balance, behind surplus, sweep and add_tranche.
+    assert_eq!(client.try_locked(&token_id), Err(Ok(Error::ArithmeticOverflow)));
+    assert_eq!(client.try_surplus(), Err(Ok(Error::ArithmeticOverflow)));
+    set_tranche(&Tranche { pool: i128::MAX, emitted: 0, ..tranche.clone() });
+    assert_eq!(client.try_locked(&token_id), Err(Ok(Error::ArithmeticOverflow)));
+    assert_eq!(client.try_add_tranche(&2, &token_id, &fund, &committee, &1_000, &origin, &params.half_life),
+        Err(Ok(Error::ArithmeticOverflow)));
+
+    // In range again, everything resumes.
+    set_tranche(&tranche);
+    assert!(client.release_all().get(token_id).unwrap() > 0);
+    assert_eq!(client.surplus(), 0);
+}
+
+#[test]
+fn test_emission_rebase() {
+    let half_life = EmissionParams::default().half_life;
+    let year = 6_307_200u64;
+
+    // Same pool: the inverse recovers the elapsed time.
+    for t in [1, 1_000, year, 7 * year, 50 * year] {
+        let emitted = emission::delta(POOL, half_life, 0, t, 0, 0).unwrap();
+        assert_eq!(emission::elapsed(POOL, half_life, emitted).unwrap(), t);
+    }
+
+    // Larger pool: emitted-to-date is bracketed within one ledger, and
+    // the extra supply is released over the tail.
+    for amount in [1, 1_000_000, POOL / 10, POOL] {
+        let pool = POOL + amount;
+        for t in [0, 1, year, 7 * year, 50 * year] {
+            let emitted = emission::delta(POOL, half_life, 0, t, 0, 0).unwrap();
+            let elapsed = emission::elapsed(pool, half_life, emitted).unwrap();
+            assert!(elapsed <= t);
+            assert!(emission::delta(pool, half_life, 0, elapsed, 0, 0).unwrap() <= emitted);
+            assert!(emission::delta(pool, half_life, 0, elapsed + 1, 0, 0).unwrap() > emitted);
+
+            // Up to the amount more over the tail, less at most the
+            // one-ledger lag of rounding the elapsed time down.
+            let lag = emission::delta(POOL, half_life, 0, t + 1, 0, emitted).unwrap();
+            let tail = emission::delta(pool, half_life, 0, elapsed + 1_000 * year, 0, emitted).unwrap();
+            let before = emission::delta(POOL, half_life, 0, t + 1_000 * year, 0, emitted).unwrap();
+            assert!((-lag..=amount).contains(&(tail - before)));
+        }
+    }
+
+    assert_eq!(emission::elapsed(POOL, half_life, POOL), Err(Error::ArithmeticOverflow));
+    assert_eq!(emission::elapsed(POOL, half_life, -1), Err(Error::ArithmeticOverflow));
 }
 
 #[test]
@@ -157,11 +285,12 @@ fn test_emission_curve_50y() {
         (310052801, 316360001, 21491228099988),
     ];
 
+    let half_life = EmissionParams::default().half_life;
     let mut realized: i128 = 0;
     let mut prev: u32 = 1000001;
     for (origin, target, expected) in REF {
         assert_eq!(*origin, prev, "not contiguous {} -> {}", origin, target);
-        let delta = emission::delta(1000000u32, *target, realized);
+        let delta = emission::delta(POOL, half_life, 1000000u64, *target as u64, 0, realized).unwrap();
         assert_eq!(delta, *expected, "mismatch for {} -> {}", origin, target);
         realized += delta;
         prev = *target;
@@ -224,11 +353,12 @@ fn test_emission_curve_random() {
         (202043223, 205516115, 24644812619312),
     ];
 
+    let half_life = EmissionParams::default().half_life;
     let mut realized: i128 = 0;
     let mut prev: u32 = 1000001;
     for (origin, target, expected) in REF {
         assert_eq!(*origin, prev, "not contiguous {} -> {}", origin, target);
-        let delta = emission::delta(1000000u32, *target, realized);
+        let delta = emission::delta(POOL, half_life, 1000000u64, *target as u64, 0, realized).unwrap();
         assert_eq!(delta, *expected, "mismatch for {} -> {}", origin, target);
         realized += delta;
         prev = *target;
@@ -238,23 +368,7 @@ fn test_emission_curve_random() {
 #[test]
 fn test_invariants() {
     let params = EmissionParams::default();
-    let env = Env::default();
-    let contract_id = env.register(Escrow, ());
-    let client = EscrowClient::new(&env, &contract_id);
-    let token_admin = Address::generate(&env);
-    let token_id = env.register_stellar_asset_contract_v2(token_admin.clone()).address();
-    let token = token::Client::new(&env, &token_id);
-    let token_admin_client = token::StellarAssetClient::new(&env, &token_id);
-    let admin = Address::generate(&env);
-    let fund = Address::generate(&env);
-    let rewards = Address::generate(&env);
-    let ledger: u32 = 1_000;
-
-    env.mock_all_auths();
-    env.ledger().set_sequence_number(ledger);
-    token_admin_client.mint(&admin, &params.total_supply);
-
-    client.initialize(&admin, &token_id, &fund, &rewards, &ledger);
+    let Setup { env, client, contract_id, token, fund, rewards, .. } = setup();
 
     // Check allocation payment.
     let allocation = params.allocation;
@@ -265,8 +379,8 @@ fn test_invariants() {
     let r0 = client.release();
     assert_eq!(r0, 0);
 
-    // Release at ledger + 1000.
-    env.ledger().set_sequence_number(ledger + 1_000);
+    // Release at LEDGER + 1000.
+    env.ledger().set_sequence_number(LEDGER + 1_000);
 
     let r1 = client.release();
     assert!(r1 > 0);
@@ -284,7 +398,7 @@ fn test_invariants() {
     assert_eq!(rewards_balance, expected_rewards, "rewards should receive 99%");
 
     // Idempotence.
-    env.ledger().set_sequence_number(ledger + 2_001);
+    env.ledger().set_sequence_number(LEDGER + 2_001);
 
     let r3 = client.release();
     let r4 = client.release();
@@ -294,45 +408,1086 @@ fn test_invariants() {
 }
 
 #[test]
-#[should_panic]
 fn test_double_init() {
-    let env = Env::default();
-    let contract_id = env.register(Escrow, ());
-    let client = EscrowClient::new(&env, &contract_id);
-    let token_admin = Address::generate(&env);
-    let token_id = env.register_stellar_asset_contract_v2(token_admin.clone()).address();
-    let admin = Address::generate(&env);
-    let fund = Address::generate(&env);
-    let rewards = Address::generate(&env);
-    let ledger: u32 = 1_000;
+    let Setup { client, token_id, admin, fund, rewards, .. } = setup();
+    assert_eq!(client.try_initialize(&admin, &token_id, &fund, &rewards, &LEDGER),
+        Err(Ok(Error::AlreadyInitialized)));
+}
+
+#[test]
+fn test_upgrade() {
+    let Setup { env, client, .. } = setup();
+    let wasm = include_bytes!("../../../target/wasm32-unknown-unknown/release/escrow.wasm");
+    let wasm_hash = env.deployer().upload_contract_wasm(Bytes::from_slice(&env, wasm));
+
+    client.upgrade(&wasm_hash);
+    env.ledger().set_sequence_number(LEDGER + 1_000);
+    assert!(client.release() >= 0);
+}
+
+#[test]
+fn test_rate_and_forecast() {
+    let params = EmissionParams::default();
+    let Setup { env, client, .. } = setup();
+
+    // Rate is the emission of a single ledger and decays over time.
+    let origin = LEDGER as u64;
+    assert_eq!(client.rate_at(&(origin - 1)), 0);
+    assert_eq!(client.rate_at(&origin), emission::delta(POOL, params.half_life, origin, origin + 1, 0, 0).unwrap());
+    assert!(client.rate_at(&origin) > client.rate_at(&(origin + 6_307_200)));
+
+    // Forecast matches the next release.
+    assert_eq!(client.forecast(&0), 0);
+    let forecast = client.forecast(&500);
+    env.ledger().set_sequence_number(LEDGER + 500);
+    assert_eq!(client.forecast(&0), forecast);
+    assert_eq!(client.release(), forecast);
+    assert_eq!(client.forecast(&0), 0);
+    assert_eq!(client.forecast(&1), client.rate_at(&(origin + 500)));
+}
+
+
+#[test]
+fn test_timestamp_schedule() {
+    // (close time, ledgers) covering the same 1,200,000 seconds.
+    const CLOSE_TIMES: [(u64, u32); 4] = [(5, 240_000), (6, 200_000), (4, 300_000), (8, 150_000)];
+    const STEPS: u32 = 25;
+
+    let params = EmissionParams::timestamp();
+    let origin = 1_700_000_000u64;
+    let elapsed = 1_200_000u64;
+    let expected = emission::delta(POOL, params.half_life, origin, origin + elapsed, 0, 0).unwrap();
+    assert!(expected > 0);
+
+    for (close_time, ledgers) in CLOSE_TIMES {
+        let Setup { env, client, token_id, token, admin, fund, rewards, .. } = deploy(Escrow);
+        let mut ledger = 1_000u32;
+        let mut timestamp = origin;
+
+        env.ledger().set_sequence_number(ledger);
+        env.ledger().set_timestamp(timestamp);
+        client.initialize_timestamp(&admin, &token_id, &fund, &rewards, &origin);
+
+        let mut released = 0i128;
+        for _ in 0..STEPS {
+            ledger += ledgers / STEPS;
+            timestamp += close_time * (ledgers / STEPS) as u64;
+            env.ledger().set_sequence_number(ledger);
+            env.ledger().set_timestamp(timestamp);
+            released += client.release();
+        }
+
+        assert_eq!(timestamp, origin + elapsed);
+        assert_eq!(released, expected, "close time {}s", close_time);
+        assert_eq!(token.balance(&fund) + token.balance(&rewards), params.allocation + expected);
+
+        // Clamp: no emission before the origin nor when time stalls.
+        assert_eq!(client.release(), 0);
+        assert_eq!(client.rate_at(&(origin - 1)), 0);
+        env.ledger().set_sequence_number(ledger + 10);
+        assert_eq!(client.release(), 0);
+    }
+
+    // One year of seconds releases one year of 5-second ledgers.
+    let ledger_params = EmissionParams::of(Clock::Ledger);
+    assert_eq!(emission::delta(POOL, params.half_life, 0, 31_536_000, 0, 0).unwrap(),
+        emission::delta(POOL, ledger_params.half_life, 0, 6_307_200, 0, 0).unwrap());
+}
+
+#[test]
+fn test_pause_shifts_curve() {
+    let params = EmissionParams::default();
+    let Setup { env, client, contract_id, token, fund, rewards, .. } = setup();
+    let origin = LEDGER as u64;
+
+    assert!(client.try_unpause().is_err());
+
+    // Pause after 1,000 ledgers, for 5,000 ledgers.
+    env.ledger().set_sequence_number(LEDGER + 1_000);
+    let r0 = client.release();
+    client.pause();
+    assert!(client.is_paused());
+    assert!(client.try_pause().is_err());
+
+    env.ledger().set_sequence_number(LEDGER + 3_000);
+    assert_eq!(client.release(), 0);
+    assert_eq!(client.forecast(&1_000), 0);
+    assert_eq!(client.rate_at(&(origin + 3_000)), 0);
+
+    env.ledger().set_sequence_number(LEDGER + 6_000);
+    let balance = token.balance(&contract_id);
+    assert_eq!(client.release(), 0);
+    assert_eq!(token.balance(&contract_id), balance);
+    client.unpause();
+    assert!(!client.is_paused());
+
+    // Resuming continues the curve instead of dumping the paused period.
+    assert_eq!(client.release(), 0);
+    assert_eq!(client.rate_at(&(origin + 6_000)), emission::delta(POOL, params.half_life,
+        origin, origin + 1_001, 0, emission::delta(POOL, params.half_life, origin, origin + 1_000, 0, 0).unwrap()).unwrap());
+
+    env.ledger().set_sequence_number(LEDGER + 7_000);
+    let r1 = client.release();
+    assert_eq!(r0 + r1, emission::delta(POOL, params.half_life, origin, origin + 2_000, 0, 0).unwrap());
+    assert_eq!(token.balance(&fund) + token.balance(&rewards), params.allocation + r0 + r1);
+
+    // Rates read under the pauses in effect then: none before the
+    // pause, halted during it.
+    let before = emission::delta(POOL, params.half_life, origin, origin + 200, 0, 0).unwrap();
+    assert_eq!(client.rate_at(&(origin + 200)),
+        emission::delta(POOL, params.half_life, origin, origin + 201, 0, before).unwrap());
+    assert_eq!(client.rate_at(&(origin + 3_000)), 0);
+
+    // Accrual left unreleased at a pause is not forecast until resumed.
+    env.ledger().set_sequence_number(LEDGER + 8_000);
+    let accrued = client.forecast(&0);
+    assert!(accrued > 0);
+    client.pause();
+    assert_eq!(client.forecast(&0), 0);
+    assert_eq!(client.forecast(&100_000), 0);
+    assert_eq!(client.release(), 0);
+    client.unpause();
+    assert_eq!(client.forecast(&0), accrued);
+    assert_eq!(client.release(), accrued);
+}
+
+#[test]
+fn test_origin_validation() {
+    let params = EmissionParams::default();
+    let Setup { env, client, token_id, token, admin, fund, rewards, .. } = deploy(Escrow);
+    let ledger: u32 = 100_000;
 
-    env.mock_all_auths();
     env.ledger().set_sequence_number(ledger);
-    client.initialize(&admin, &token_id, &fund, &rewards, &ledger);
-    client.initialize(&admin, &token_id, &fund, &rewards, &ledger);
+
+    let too_early = ledger - params.origin_tolerance as u32 - 1;
+    let too_late = ledger + params.origin_horizon as u32 + 1;
+    assert_eq!(client.try_initialize(&admin, &token_id, &fund, &rewards, &too_early),
+        Err(Ok(Error::OriginTooEarly)));
+    assert_eq!(client.try_initialize(&admin, &token_id, &fund, &rewards, &too_late),
+        Err(Ok(Error::OriginTooLate)));
+    assert_eq!(token.balance(&admin), params.total_supply);
+
+    // Tolerance and horizon bounds are inclusive.
+    let earliest = ledger - params.origin_tolerance as u32;
+    client.initialize(&admin, &token_id, &fund, &rewards, &earliest);
+    assert_eq!(client.forecast(&0), emission::delta(POOL, params.half_life,
+        earliest as u64, ledger as u64, 0, 0).unwrap());
+
+    // Initialization is checked before the origin.
+    assert_eq!(client.try_initialize(&admin, &token_id, &fund, &rewards, &too_early),
+        Err(Ok(Error::AlreadyInitialized)));
+    assert_eq!(client.try_initialize_timestamp(&admin, &token_id, &fund, &rewards, &0),
+        Err(Ok(Error::AlreadyInitialized)));
 }
 
 #[test]
-fn test_upgrade() {
+fn test_cliff() {
     let params = EmissionParams::default();
-    let env = Env::default();
-    let contract_id = env.register(Escrow, ());
-    let client = EscrowClient::new(&env, &contract_id);
-    let token_admin = Address::generate(&env);
-    let token_id = env.register_stellar_asset_contract_v2(token_admin.clone()).address();
-    let token_admin_client = token::StellarAssetClient::new(&env, &token_id);
-    let admin = Address::generate(&env);
-    let fund = Address::generate(&env);
-    let rewards = Address::generate(&env);
-    let start_ledger = 1_000u32;
+    let origin = LEDGER as u64;
+    let cliff = 10_000u64;
+
+    for catch_up in [true, false] {
+        let Setup { env, client, token_id, admin, fund, rewards, .. } = deploy(Escrow);
+        client.initialize(&admin, &token_id, &fund, &rewards, &(LEDGER + 100));
+
+        assert_eq!(client.try_set_cliff(&(params.origin_horizon + 1), &catch_up),
+            Err(Ok(Error::InvalidCliff)));
+        client.set_cliff(&cliff, &catch_up);
+
+        // Nothing is emitted until origin + cliff.
+        let start = origin + 100 + cliff;
+        env.ledger().set_sequence_number(start as u32 - 1);
+        assert_eq!(client.release(), 0);
+        assert_eq!(client.rate_at(&(start - 2)), 0);
+        assert_eq!(client.try_set_cliff(&0, &catch_up), Err(Ok(Error::AlreadyStarted)));
+
+        env.ledger().set_sequence_number(start as u32 + 500);
+        let expected = if catch_up {
+            emission::delta(POOL, params.half_life, origin + 100, start + 500, 0, 0).unwrap()
+        } else {
+            emission::delta(POOL, params.half_life, start, start + 500, 0, 0).unwrap()
+        };
+        assert_eq!(client.release(), expected);
+    }
+}
+
+#[test]
+fn test_release_cap() {
+    let params = EmissionParams::default();
+    let origin = LEDGER as u64;
+    let gap = 100_000u32;
+    let expected = emission::delta(POOL, params.half_life, origin, origin + gap as u64, 0, 0).unwrap();
+
+    for cap in [ReleaseCap::Amount(expected / 7), ReleaseCap::Duration(10_000)] {
+        let Setup { env, client, token, fund, rewards, .. } = setup();
+
+        assert_eq!(client.try_set_release_cap(&Some(ReleaseCap::Amount(0))), Err(Ok(Error::InvalidCap)));
+        assert_eq!(client.try_set_release_cap(&Some(ReleaseCap::Duration(0))), Err(Ok(Error::InvalidCap)));
+        client.set_release_cap(&Some(cap));
+
+        // A long gap is paid out over several capped releases.
+        env.ledger().set_sequence_number(LEDGER + gap);
+        let limit = match cap {
+            ReleaseCap::Amount(amount) => amount,
+            ReleaseCap::Duration(duration) => emission::delta(POOL, params.half_life, origin,
+                origin + gap as u64, 0, emission::delta(POOL, params.half_life, origin,
+                    origin + gap as u64 - duration, 0, 0).unwrap()).unwrap(),
+        };
+        assert_eq!(client.forecast(&0), limit);
+
+        let mut released = 0i128;
+        let mut calls = 0;
+        loop {
+            let delta = client.release();
+            if delta == 0 {
+                break;
+            }
+            assert!(delta <= limit);
+            released += delta;
+            calls += 1;
+        }
+
+        // The cumulative curve stays authoritative.
+        assert!(calls > 1);
+        assert_eq!(released, expected);
+        assert_eq!(token.balance(&fund) + token.balance(&rewards), params.allocation + expected);
+
+        // Removing the cap releases everything accrued.
+        client.set_release_cap(&Some(cap));
+        env.ledger().set_sequence_number(LEDGER + 2 * gap);
+        let r0 = client.release();
+        client.set_release_cap(&None);
+        let r1 = client.release();
+        assert_eq!(released + r0 + r1, emission::delta(POOL, params.half_life, origin,
+            origin + 2 * gap as u64, 0, 0).unwrap());
+    }
+}
+
+#[test]
+fn test_release_threshold() {
+    let params = EmissionParams::default();
+    let Setup { env, client, contract_id, token, .. } = setup();
+    let origin = LEDGER as u64;
+
+    let invalid = ReleaseThreshold { min_delta: -1, min_gap: 0 };
+    assert_eq!(client.try_set_release_threshold(&Some(invalid)), Err(Ok(Error::InvalidThreshold)));
+
+    // Ten ledgers worth of emission, and at least twenty ledgers apart.
+    let min_delta = emission::delta(POOL, params.half_life, origin, origin + 10, 0, 0).unwrap();
+    client.set_release_threshold(&Some(ReleaseThreshold { min_delta, min_gap: 20 }));
+
+    // Dust releases leave balances untouched.
+    let balance = token.balance(&contract_id);
+    for step in 1..10 {
+        env.ledger().set_sequence_number(LEDGER + step);
+        assert_eq!(client.release(), 0);
+        assert_eq!(client.forecast(&0), 0);
+    }
+    assert_eq!(token.balance(&contract_id), balance);
+
+    // Accrual is kept for the next eligible release.
+    env.ledger().set_sequence_number(LEDGER + 10);
+    let r0 = client.release();
+    assert_eq!(r0, min_delta);
+
+    // Minimum gap between releases.
+    env.ledger().set_sequence_number(LEDGER + 29);
+    assert_eq!(client.release(), 0);
+    env.ledger().set_sequence_number(LEDGER + 30);
+    let r1 = client.release();
+    assert_eq!(r0 + r1, emission::delta(POOL, params.half_life, origin, origin + 30, 0, 0).unwrap());
+
+    client.set_release_threshold(&None);
+    env.ledger().set_sequence_number(LEDGER + 31);
+    assert!(client.release() > 0);
+}
+
+#[test]
+fn test_release_history() {
+    let params = EmissionParams::default();
+    let Setup { env, client, .. } = setup();
+
+    // Empty releases are not recorded.
+    assert_eq!(client.release(), 0);
+    assert_eq!(client.release_count(), 0);
+    assert_eq!(client.releases(&0, &10).len(), 0);
+
+    let mut cumulative = 0i128;
+    for step in 1..=150u32 {
+        env.ledger().set_sequence_number(LEDGER + step * 7);
+        env.ledger().set_timestamp(step as u64 * 35);
+        cumulative += client.release();
+    }
+    assert_eq!(client.release_count(), 150);
+
+    // Pages are bounded and records chain up to the emitted total.
+    let first = client.releases(&0, &1_000);
+    let last = client.releases(&100, &100);
+    assert_eq!(first.len(), 100);
+    assert_eq!(last.len(), 50);
+    assert_eq!(client.releases(&150, &10).len(), 0);
+
+    let mut total = 0i128;
+    for (index, release) in first.iter().chain(last.iter()).enumerate() {
+        let step = index as u32 + 1;
+        assert_eq!(release.ledger, LEDGER + step * 7);
+        assert_eq!(release.timestamp, step as u64 * 35);
+        assert_eq!(release.fund + release.rewards, release.delta);
+        assert_eq!(release.fund, release.delta * params.fund_bps / 10_000);
+        total += release.delta;
+        assert_eq!(release.cumulative, total);
+    }
+    assert_eq!(total, cumulative);
+}
+
+#[test]
+fn test_emitted_at() {
+    let params = EmissionParams::default();
+    let Setup { env, client, .. } = setup();
+    let origin = LEDGER as u64;
+    let curve = |at: u64| emission::delta(POOL, params.half_life, origin, at, 0, 0).unwrap();
+
+    // No checkpoints yet: the curve alone.
+    assert_eq!(client.emitted_at(&(origin - 1)), 0);
+    assert_eq!(client.emitted_at(&(origin + 500)), curve(origin + 500));
+
+    let mut cumulative = 0i128;
+    for step in [300u32, 1_200, 5_000, 5_100, 20_000] {
+        env.ledger().set_sequence_number(LEDGER + step);
+        cumulative += client.release();
+        assert_eq!(client.emitted_at(&(origin + step as u64)), cumulative);
+    }
+
+    // Between checkpoints the curve interpolates, monotonically.
+    let mut prev = 0i128;
+    for offset in (0..25_000u64).step_by(250) {
+        let emitted = client.emitted_at(&(origin + offset));
+        assert_eq!(emitted, curve(origin + offset));
+        assert!(emitted >= prev);
+        prev = emitted;
+    }
+
+    // Paused periods are flat, earlier history is unchanged.
+    client.pause();
+    env.ledger().set_sequence_number(LEDGER + 30_000);
+    client.unpause();
+    env.ledger().set_sequence_number(LEDGER + 31_000);
+    cumulative += client.release();
+    assert_eq!(cumulative, curve(origin + 21_000));
+    assert_eq!(client.emitted_at(&(origin + 10_000)), curve(origin + 10_000));
+    assert_eq!(client.emitted_at(&(origin + 25_000)), curve(origin + 20_000));
+    assert_eq!(client.emitted_at(&(origin + 31_000)), cumulative);
+    assert_eq!(client.emitted_at(&(origin + 32_000)), curve(origin + 22_000));
+}
+
+#[test]
+fn test_sweep() {
+    let params = EmissionParams::default();
+    let Setup { env, client, contract_id, token_admin, token_id, token, token_admin_client, fund,
+        rewards, .. } = setup();
+    let foreign_id = env.register_stellar_asset_contract_v2(token_admin.clone()).address();
+    let foreign = token::Client::new(&env, &foreign_id);
+    let foreign_admin_client = token::StellarAssetClient::new(&env, &foreign_id);
+
+    // The escrowed token is never swept, even its surplus.
+    token_admin_client.mint(&contract_id, &1_000);
+    assert_eq!(client.try_sweep(&token_id), Err(Ok(Error::InvalidToken)));
+    assert_eq!(token.balance(&contract_id), params.pool() + 1_000);
+
+    // Foreign tokens go to the fund in full.
+    assert_eq!(client.sweep(&foreign_id), 0);
+    foreign_admin_client.mint(&contract_id, &5_000);
+    assert_eq!(client.sweep(&foreign_id), 5_000);
+    assert_eq!(foreign.balance(&contract_id), 0);
+    assert_eq!(foreign.balance(&fund), 5_000);
+    assert_eq!(foreign.balance(&rewards), 0);
+    assert_eq!(client.sweep(&foreign_id), 0);
+
+    // Releases are unaffected.
+    env.ledger().set_sequence_number(LEDGER + 1_000);
+    let delta = client.release();
+    assert_eq!(delta, emission::delta(POOL, params.half_life, LEDGER as u64, LEDGER as u64 + 1_000, 0, 0).unwrap());
+}
+
+#[test]
+fn test_surplus() {
+    let params = EmissionParams::default();
+    let Setup { env, client, contract_id, token, token_admin_client, fund, rewards, .. } = setup();
+    let origin = LEDGER as u64;
+    assert_eq!(client.surplus(), 0);
+    assert_eq!(client.reconcile(), 0);
+
+    // Donations show up as surplus, releases do not.
+    env.ledger().set_sequence_number(LEDGER + 1_000);
+    let r0 = client.release();
+    assert_eq!(client.surplus(), 0);
+    token_admin_client.mint(&contract_id, &5_000);
+    assert_eq!(client.surplus(), 5_000);
+
+    // Routed to the fund by default.
+    let fund_balance = token.balance(&fund);
+    assert_eq!(client.reconcile(), 5_000);
+    assert_eq!(token.balance(&fund), fund_balance + 5_000);
+    assert_eq!(client.surplus(), 0);
+
+    // Folded into rewards.
+    client.set_surplus_policy(&SurplusPolicy::Rewards);
+    token_admin_client.mint(&contract_id, &7_000);
+    let rewards_balance = token.balance(&rewards);
+    assert_eq!(client.reconcile(), 7_000);
+    assert_eq!(token.balance(&rewards), rewards_balance + 7_000);
+
+    // The curve is untouched by either policy.
+    env.ledger().set_sequence_number(LEDGER + 3_000);
+    let r1 = client.release();
+    assert_eq!(r0 + r1, emission::delta(POOL, params.half_life, origin, origin + 3_000, 0, 0).unwrap());
+    assert_eq!(token.balance(&contract_id), POOL - r0 - r1);
+}
+
+#[test]
+fn test_surplus_top_up() {
+    const DONATION: i128 = 1_000_000_000_000;
+
+    let params = EmissionParams::default();
+    let Setup { env, client, contract_id, token, token_admin_client, fund, rewards, .. } = setup();
+    let year = 6_307_200u32;
+    client.set_surplus_policy(&SurplusPolicy::TopUp);
+
+    // Mid-curve top-up: no jump, a faster tail.
+    env.ledger().set_sequence_number(LEDGER + 3 * year);
+    let r0 = client.release();
+    let rate = client.rate_at(&((LEDGER + 3 * year) as u64));
+    token_admin_client.mint(&contract_id, &DONATION);
+    assert_eq!(client.surplus(), DONATION);
+    assert_eq!(client.reconcile(), DONATION);
+    assert_eq!(client.surplus(), 0);
+    assert_eq!(token.balance(&contract_id), POOL + DONATION - r0);
+    assert_eq!(client.forecast(&0), 0);
+    assert!(client.forecast(&1) <= rate * 2);
+    assert!(client.rate_at(&((LEDGER + 3 * year) as u64)) > rate);
+
+    // The tail releases the donation along with the pool.
+    let mut released = r0;
+    for years in [4, 10, 100, 600] {
+        env.ledger().set_sequence_number(LEDGER + years * year);
+        released += client.release();
+    }
+
+    let at = (LEDGER + 600 * year) as u64;
+    assert!(released > emission::delta(POOL, params.half_life, LEDGER as u64, at, 0, 0).unwrap());
+    assert!(released < POOL + DONATION);
+    assert_eq!(token.balance(&fund) + token.balance(&rewards) + token.balance(&contract_id),
+        params.total_supply + DONATION);
+    assert_eq!(client.surplus(), 0);
+
+    // Below min_top_up the surplus waits for more.
+    token_admin_client.mint(&contract_id, &(params.min_top_up - 1));
+    assert_eq!(client.reconcile(), 0);
+    assert_eq!(client.surplus(), params.min_top_up - 1);
+    token_admin_client.mint(&contract_id, &1);
+    assert_eq!(client.reconcile(), params.min_top_up);
+    assert_eq!(client.surplus(), 0);
+
+    // Before origin the pool simply grows.
+    let Setup { env, client, contract_id, token_id, token_admin_client, admin, fund, rewards, .. } = deploy(Escrow);
+    let origin = (LEDGER + 1_000) as u64;
+
+    client.initialize(&admin, &token_id, &fund, &rewards, &(origin as u32));
+    client.set_surplus_policy(&SurplusPolicy::TopUp);
+    token_admin_client.mint(&contract_id, &DONATION);
+    assert_eq!(client.reconcile(), DONATION);
+
+    env.ledger().set_sequence_number(origin as u32 + year);
+    assert_eq!(client.release(),
+        emission::delta(POOL + DONATION, params.half_life, origin, origin + year as u64, 0, 0).unwrap());
+}
+
+#[test]
+fn test_top_up() {
+    const AMOUNT: i128 = 2_000_000_000_000_000;
+
+    let params = EmissionParams::default();
+    let Setup { env, client, contract_id, token, token_admin_client, fund, rewards, .. } = setup();
+    let treasury = Address::generate(&env);
+    let year = 6_307_200u32;
+    let now = (LEDGER + 2 * year) as u64;
+
+    token_admin_client.mint(&treasury, &AMOUNT);
+
+    assert_eq!(client.try_top_up(&treasury, &0), Err(Ok(Error::InvalidAmount)));
+    assert_eq!(client.try_top_up(&treasury, &-1), Err(Ok(Error::InvalidAmount)));
+    assert_eq!(client.try_top_up(&treasury, &(params.min_top_up - 1)), Err(Ok(Error::InvalidAmount)));
+
+    env.ledger().set_sequence_number(now as u32);
+    let r0 = client.release();
+    let rate = client.rate_at(&now);
+    let ahead = client.forecast(&1_000);
+    client.top_up(&treasury, &AMOUNT);
+    assert_eq!(token.balance(&treasury), 0);
+    assert_eq!(token.balance(&contract_id), POOL + AMOUNT - r0);
+    assert_eq!(client.surplus(), 0);
+
+    // No discontinuity: emitted-to-date is unchanged and the curve
+    // resumes from it, only faster.
+    assert_eq!(client.emitted_at(&now), r0);
+    assert_eq!(client.forecast(&0), 0);
+    assert!(client.forecast(&1) <= client.rate_at(&now));
+    assert!(client.rate_at(&now) > rate);
+    assert!(client.forecast(&1_000) > ahead);
+    assert!(client.forecast(&1_000) < ahead * (POOL + AMOUNT) / (POOL - r0) + 1_000);
+
+    // Same half-life shape over the tail: the rebased curve plus the
+    // rounding carry, and the donation is released along with the pool.
+    let elapsed = emission::elapsed(POOL + AMOUNT, params.half_life, r0).unwrap();
+    let carry = r0 - emission::delta(POOL + AMOUNT, params.half_life, 0, elapsed, 0, 0).unwrap();
+    let mut released = r0;
+    for years in [3, 10, 100, 600] {
+        let at = (LEDGER + years * year) as u64;
+        env.ledger().set_sequence_number(at as u32);
+        released += client.release();
+        let expected = emission::delta(POOL + AMOUNT, params.half_life, 0, elapsed + at - now, 0, 0).unwrap();
+        assert_eq!(released, (expected + carry).min(POOL + AMOUNT));
+    }
+
+    assert!(released > POOL);
+    assert_eq!(token.balance(&fund) + token.balance(&rewards) + token.balance(&contract_id),
+        params.total_supply + AMOUNT);
+}
+
+#[test]
+fn test_top_up_never_rewinds() {
+    let params = EmissionParams::default();
+    let Setup { env, client, contract_id, token, token_admin_client, .. } = setup();
+    let treasury = Address::generate(&env);
+    let now = (LEDGER + 2 * 6_307_200) as u64;
+
+    env.ledger().set_sequence_number(now as u32);
+    let r0 = client.release();
+
+    // Dust neither tops up nor rebases.
+    token_admin_client.mint(&treasury, &(params.min_top_up - 1));
+    assert_eq!(client.try_top_up(&treasury, &(params.min_top_up - 1)), Err(Ok(Error::InvalidAmount)));
+    client.set_surplus_policy(&SurplusPolicy::TopUp);
+    token_admin_client.mint(&contract_id, &(params.min_top_up - 1));
+    assert_eq!(client.reconcile(), 0);
+    assert_eq!(client.surplus(), params.min_top_up - 1);
+    client.set_surplus_policy(&SurplusPolicy::Fund);
+    client.reconcile();
+
+    // Repeated minimal top-ups, directly and through reconcile, each
+    // keep emitted-to-date exact and never lower the forecast.
+    let mut ahead = client.forecast(&100);
+    for round in 0..200 {
+        if round % 2 == 0 {
+            token_admin_client.mint(&treasury, &params.min_top_up);
+            client.top_up(&treasury, &params.min_top_up);
+        } else {
+            client.set_surplus_policy(&SurplusPolicy::TopUp);
+            token_admin_client.mint(&contract_id, &params.min_top_up);
+            assert_eq!(client.reconcile(), params.min_top_up);
+        }
+
+        assert_eq!(client.emitted_at(&now), r0);
+        assert_eq!(client.forecast(&0), 0);
+        let next = client.forecast(&100);
+        assert!(next >= ahead);
+        ahead = next;
+    }
+
+    assert_eq!(token.balance(&contract_id), client.locked(&token.address));
+}
+
+#[test]
+fn test_set_half_life() {
+    let params = EmissionParams::default();
+    let Setup { env, client, contract_id, token, fund, rewards, .. } = setup();
+    let year = 6_307_200u32;
+    let now = (LEDGER + 2 * year) as u64;
+    let longer = params.half_life * 5 / 4;
+    assert_eq!(client.half_life(), params.half_life);
+
+    // At most half_life_step_bps per change.
+    assert_eq!(client.try_set_half_life(&(longer + 1)), Err(Ok(Error::InvalidHalfLife)));
+    assert_eq!(client.try_set_half_life(&(params.half_life * 3 / 4 - 1)), Err(Ok(Error::InvalidHalfLife)));
+
+    env.ledger().set_sequence_number(now as u32);
+    let r0 = client.release();
+    let rate = client.rate_at(&now);
+    client.set_half_life(&longer);
+    assert_eq!(client.half_life(), longer);
+
+    // Continuous at the change, only the slope moves.
+    assert_eq!(client.emitted_at(&now), r0);
+    assert_eq!(client.forecast(&0), 0);
+    assert!(client.forecast(&1) <= client.rate_at(&now));
+    assert!(client.rate_at(&now) < rate);
+
+    // At most once per retune_period.
+    assert_eq!(client.try_set_half_life(&params.half_life), Err(Ok(Error::HalfLifeLocked)));
+    env.ledger().set_sequence_number(now as u32 + year - 1);
+    assert_eq!(client.try_set_half_life(&params.half_life), Err(Ok(Error::HalfLifeLocked)));
+
+    // The tail follows the new half-life from the rebased elapsed time.
+    let elapsed = emission::elapsed(POOL, longer, r0).unwrap();
+    let at = (LEDGER + 3 * year) as u64;
+    env.ledger().set_sequence_number(at as u32);
+    let r1 = client.release();
+    assert_eq!(r0 + r1, emission::delta(POOL, longer, 0, elapsed + at - now, 0, 0).unwrap());
+    assert!(r0 + r1 < emission::delta(POOL, params.half_life, LEDGER as u64, at, 0, 0).unwrap());
+
+    // Shortened back, releases resume on the faster slope.
+    let rate = client.rate_at(&at);
+    client.set_half_life(&params.half_life);
+    assert_eq!(client.forecast(&0), 0);
+    assert!(client.rate_at(&at) > rate);
+    env.ledger().set_sequence_number(LEDGER + 600 * year);
+    let r2 = client.release();
+    assert!(r0 + r1 + r2 < POOL);
+    assert_eq!(token.balance(&fund) + token.balance(&rewards) + token.balance(&contract_id),
+        params.total_supply);
+
+    // Before origin the half-life simply changes.
+    let Setup { env, client, token_id, admin, fund, rewards, .. } = deploy(Escrow);
+    let origin = (LEDGER + 1_000) as u64;
+
+    client.initialize(&admin, &token_id, &fund, &rewards, &(origin as u32));
+    client.set_half_life(&longer);
+
+    env.ledger().set_sequence_number(origin as u32 + year);
+    assert_eq!(client.release(), emission::delta(POOL, longer, origin, origin + year as u64, 0, 0).unwrap());
+}
+
+#[test]
+fn test_set_half_life_bounds() {
+    let params = EmissionParams::default();
+    let Setup { env, client, contract_id, token, fund, rewards, .. } = setup();
+    let year = 6_307_200u32;
+
+    assert_eq!(client.try_set_half_life(&0), Err(Ok(Error::InvalidHalfLife)));
+    assert_eq!(client.try_set_half_life(&-1), Err(Ok(Error::InvalidHalfLife)));
+
+    // Yearly maximal retunes walk up to MAX_HALF_LIFE and stop there.
+    let mut at = LEDGER;
+    while client.half_life() < emission::MAX_HALF_LIFE {
+        at += year;
+        env.ledger().set_sequence_number(at);
+        let longer = (client.half_life() * 5 / 4).min(emission::MAX_HALF_LIFE);
+        client.set_half_life(&longer);
+    }
+
+    at += year;
+    env.ledger().set_sequence_number(at);
+    assert_eq!(client.try_set_half_life(&(emission::MAX_HALF_LIFE + 1)), Err(Ok(Error::InvalidHalfLife)));
+    client.set_half_life(&emission::MAX_HALF_LIFE);
+
+    // Releases stay within the curve bounds.
+    client.release();
+    assert!(client.forecast(&u64::MAX) < POOL);
+    assert_eq!(token.balance(&fund) + token.balance(&rewards) + token.balance(&contract_id),
+        params.total_supply);
+}
+
+#[test]
+fn test_tranches() {
+    const ECOSYSTEM: i128 = 1_000_000_000_000_000;
+    const GRANTS: i128 = 300_000_000_000_000;
+
+    let params = EmissionParams::default();
+    let Setup { env, client, contract_id, token_id, token, admin, fund, rewards, .. } = setup();
+    let ecosystem = Address::generate(&env);
+    let grants = Address::generate(&env);
+    let committee = Address::generate(&env);
+    let origin = LEDGER as u64;
+    let half_life = 4 * 6_307_200;
+    let primary_pool = POOL - ECOSYSTEM - GRANTS;
+
+    // Validation.
+    assert_eq!(client.try_add_tranche(&1, &token_id, &ecosystem, &ecosystem, &ECOSYSTEM, &origin, &half_life),
+        Err(Ok(Error::InvalidAddress)));
+    assert_eq!(client.try_add_tranche(&1, &token_id, &ecosystem, &committee, &0, &origin, &half_life),
+        Err(Ok(Error::InvalidTranche)));
+    assert_eq!(client.try_add_tranche(&1, &token_id, &ecosystem, &committee, &ECOSYSTEM, &origin, &0),
+        Err(Ok(Error::InvalidTranche)));
+    assert_eq!(client.try_add_tranche(&1, &token_id, &ecosystem, &committee, &ECOSYSTEM, &(origin + 6_307_201), &half_life),
+        Err(Ok(Error::OriginTooLate)));
+    assert_eq!(client.try_release_tranche(&1), Err(Ok(Error::TrancheNotFound)));
+
+    // Each tranche is carved out of the primary pool; the locked supply
+    // is unchanged and admin funds nothing.
+    client.add_tranche(&1, &token_id, &ecosystem, &committee, &ECOSYSTEM, &origin, &half_life);
+    client.add_tranche(&2, &token_id, &grants, &committee, &GRANTS, &(origin + 1_000), &params.half_life);
+    assert_eq!(client.try_add_tranche(&2, &token_id, &grants, &committee, &GRANTS, &origin, &half_life),
+        Err(Ok(Error::TrancheExists)));
+    assert_eq!(client.try_add_tranche(&3, &token_id, &grants, &committee, &primary_pool, &origin, &half_life),
+        Err(Ok(Error::InvalidTranche)));
+    assert_eq!(client.tranches().len(), 2);
+    assert_eq!(client.tranche(&1).pool, ECOSYSTEM);
+    assert_eq!(token.balance(&admin), 0);
+    assert_eq!(token.balance(&contract_id), POOL);
+    assert_eq!(client.locked(&token_id), POOL);
+    assert_eq!(client.surplus(), 0);
+
+    // Each tranche follows its own curve, the primary is unaffected.
+    env.ledger().set_sequence_number(LEDGER + 5_000);
+    let expected = emission::delta(ECOSYSTEM, half_life, origin, origin + 5_000, 0, 0).unwrap();
+    assert_eq!(client.release_tranche(&1), expected);
+    let fund_share = expected * params.fund_bps / 10_000;
+    let event = TrancheReleased { id: 1, token: token_id.clone(), delta: expected, fund: fund_share,
+        rewards: expected - fund_share, burn: 0 };
+    let events = env.events().all();
+    assert_eq!(events.slice(events.len() - 1..),
+        vec![&env, (contract_id.clone(), event.topics(&env), event.data(&env))]);
+    assert_eq!(client.release_tranche(&1), 0);
+    assert_eq!(token.balance(&ecosystem), expected * params.fund_bps / 10_000);
+    assert_eq!(token.balance(&committee), expected - expected * params.fund_bps / 10_000);
+    assert_eq!(token.balance(&fund), params.allocation);
+
+    let grants_expected = emission::delta(GRANTS, params.half_life, origin + 1_000, origin + 5_000, 0, 0).unwrap();
+    let primary = emission::delta(primary_pool, params.half_life, origin, origin + 5_000, 0, 0).unwrap();
+    assert_eq!(client.release_all(), map![&env, (token_id.clone(), primary + grants_expected)]);
+    assert_eq!(client.tranche(&2).emitted, grants_expected);
+    assert_eq!(client.release_all(), map![&env, (token_id.clone(), 0)]);
+
+    // Pauses halt and shift tranches like the primary curve.
+    client.pause();
+    env.ledger().set_sequence_number(LEDGER + 8_000);
+    assert_eq!(client.release_tranche(&1), 0);
+    client.unpause();
+    env.ledger().set_sequence_number(LEDGER + 10_000);
+    client.release_all();
+    assert_eq!(client.tranche(&1).emitted,
+        emission::delta(ECOSYSTEM, half_life, origin, origin + 10_000, 3_000, 0).unwrap());
+
+    // Each tranche keeps its own release history, apart from the primary.
+    let history = client.tranche_releases(&1, &0, &10);
+    assert_eq!(client.tranche(&1).releases, 2);
+    assert_eq!(history.len(), 2);
+    assert_eq!(history.get(0).unwrap().delta, expected);
+    assert_eq!(history.get(0).unwrap().ledger, LEDGER + 5_000);
+    assert_eq!(history.get(1).unwrap().cumulative, client.tranche(&1).emitted);
+    assert_eq!(history.get(1).unwrap().fund + history.get(1).unwrap().rewards, history.get(1).unwrap().delta);
+    assert_eq!(client.tranche_releases(&2, &0, &10).len(), 2);
+    assert_eq!(client.release_count(), 2);
+    assert_eq!(client.try_tranche_releases(&3, &0, &10), Err(Ok(Error::TrancheNotFound)));
+
+    // Locked balance covers every pool; nothing counts as surplus.
+    let owed = |id: u32| client.tranche(&id).pool - client.tranche(&id).emitted;
+    let emitted = token.balance(&fund) + token.balance(&rewards) - params.allocation;
+    assert_eq!(token.balance(&contract_id), primary_pool - emitted + owed(1) + owed(2));
+    assert_eq!(client.surplus(), 0);
+
+    // Mid-curve, the primary keeps its emission to date and slows down.
+    let now = origin + 10_000;
+    let ahead = client.forecast(&1_000);
+    client.add_tranche(&3, &token_id, &grants, &committee, &GRANTS, &now, &half_life);
+    assert_eq!(client.emitted_at(&now), emitted);
+    assert_eq!(client.forecast(&0), 0);
+    assert!(client.forecast(&1_000) < ahead);
+    assert_eq!(token.balance(&contract_id), client.locked(&token_id));
+
+    // Bounded count.
+    for id in 4..=16 {
+        client.add_tranche(&id, &token_id, &grants, &committee, &1, &origin, &half_life);
+    }
+
+    assert_eq!(client.try_add_tranche(&17, &token_id, &grants, &committee, &1, &origin, &half_life),
+        Err(Ok(Error::TooManyTranches)));
+}
+
+#[test]
+fn test_tranches_tokens() {
+    const PARTNER: i128 = 500_000_000_000;
+    const XBID: i128 = 1_000_000_000_000_000;
+
+    let params = EmissionParams::default();
+    let Setup { env, client, contract_id, token_admin, token_id, token, admin, fund, .. } = setup();
+    let partner_id = env.register_stellar_asset_contract_v2(token_admin.clone()).address();
+    let partner = token::Client::new(&env, &partner_id);
+    let partner_admin_client = token::StellarAssetClient::new(&env, &partner_id);
+    let committee = Address::generate(&env);
+    let origin = LEDGER as u64;
+    let half_life = 2 * 6_307_200;
+    partner_admin_client.mint(&admin, &(PARTNER * 2));
+
+    // A partner token stray on the escrow is swept before it gets a tranche.
+    partner_admin_client.mint(&contract_id, &7);
+    assert_eq!(client.sweep(&partner_id), 7);
+
+    // Co-incentives: one tranche per token, locked apart. The escrow
+    // token is carved from the primary pool, the partner funded by admin.
+    client.add_tranche(&1, &token_id, &fund, &committee, &XBID, &origin, &half_life);
+    client.add_tranche(&2, &partner_id, &fund, &committee, &PARTNER, &origin, &half_life);
+    assert_eq!(client.tranche(&2).token, partner_id);
+    assert_eq!(client.locked(&token_id), POOL);
+    assert_eq!(partner.balance(&admin), PARTNER);
+    assert_eq!(client.locked(&partner_id), PARTNER);
+    assert_eq!(partner.balance(&contract_id), PARTNER);
+    assert_eq!(client.surplus(), 0);
+
+    // Only the partner balance above the locked pools is swept; the
+    // escrow token goes through reconcile.
+    partner_admin_client.mint(&contract_id, &11);
+    assert_eq!(client.sweep(&partner_id), 11);
+    assert_eq!(client.sweep(&partner_id), 0);
+    assert_eq!(partner.balance(&contract_id), PARTNER);
+    assert_eq!(client.locked(&partner_id), PARTNER);
+    assert_eq!(client.try_sweep(&token_id), Err(Ok(Error::InvalidToken)));
+
+    // Per-token release: the partner tranche pays in the partner token only.
+    env.ledger().set_sequence_number(LEDGER + 5_000);
+    let expected = emission::delta(PARTNER, half_life, origin, origin + 5_000, 0, 0).unwrap();
+    let balance = token.balance(&contract_id);
+    assert_eq!(client.release_token(&partner_id), expected);
+    assert_eq!(client.release_token(&partner_id), 0);
+    assert_eq!(token.balance(&contract_id), balance);
+    assert_eq!(partner.balance(&fund), 7 + 11 + expected * params.fund_bps / 10_000);
+    assert_eq!(partner.balance(&committee), expected - expected * params.fund_bps / 10_000);
+    assert_eq!(client.locked(&partner_id), PARTNER - expected);
+
+    // The escrow token covers the primary curve and its own tranches.
+    let primary = emission::delta(POOL - XBID, params.half_life, origin, origin + 5_000, 0, 0).unwrap();
+    let xbid = emission::delta(XBID, half_life, origin, origin + 5_000, 0, 0).unwrap();
+    assert_eq!(client.release_token(&token_id), primary + xbid);
+    assert_eq!(client.locked(&token_id), POOL - primary - xbid);
+    assert_eq!(token.balance(&contract_id), client.locked(&token_id));
+    assert_eq!(client.release_all(), map![&env, (token_id.clone(), 0), (partner_id.clone(), 0)]);
+
+    // Mixed tokens under release_all, totalled per token.
+    env.ledger().set_sequence_number(LEDGER + 9_000);
+    let totals = client.release_all();
+    assert_eq!(partner.balance(&contract_id), client.locked(&partner_id));
+    assert_eq!(token.balance(&contract_id), client.locked(&token_id));
+    assert_eq!(totals.len(), 2);
+    assert_eq!(totals.get(partner_id.clone()).unwrap(),
+        emission::delta(PARTNER, half_life, origin, origin + 9_000, 0, 0).unwrap() - expected);
+    assert_eq!(totals.get(token_id.clone()).unwrap(),
+        emission::delta(POOL - XBID, params.half_life, origin, origin + 9_000, 0, 0).unwrap() - primary
+        + emission::delta(XBID, half_life, origin, origin + 9_000, 0, 0).unwrap() - xbid);
+
+    // A second partner tranche must arrive in full and keep its token covered.
+    client.add_tranche(&3, &partner_id, &fund, &committee, &PARTNER, &origin, &half_life);
+    assert_eq!(partner.balance(&contract_id), client.locked(&partner_id));
+    assert_eq!(client.locked(&Address::generate(&env)), 0);
+}
+
+#[test]
+fn test_burn() {
+    const ECOSYSTEM: i128 = 1_000_000_000_000_000;
+    let params = EmissionParams::default();
+    let Setup { env, client, contract_id, token_admin, token_id, token, admin, fund,
+        rewards, .. } = setup();
+    let origin = LEDGER as u64;
+    let supply = || token.balance(&contract_id) + token.balance(&fund) + token.balance(&rewards);
+    assert_eq!(client.burn_bps(), 0);
+
+    // The burn comes out of the rewards share; the fund share is fixed.
+    assert_eq!(client.try_set_burn_bps(&-1), Err(Ok(Error::InvalidBurn)));
+    assert_eq!(client.try_set_burn_bps(&(10_000 - params.fund_bps + 1)), Err(Ok(Error::InvalidBurn)));
+    client.set_burn_bps(&2_000);
+    assert_eq!(client.burn_bps(), 2_000);
+
+    env.ledger().set_sequence_number(LEDGER + 5_000);
+    let delta = client.release();
+    let fund_share = delta * params.fund_bps / 10_000;
+    let burn = delta * 2_000 / 10_000;
+    assert_eq!(delta, emission::delta(POOL, params.half_life, origin, origin + 5_000, 0, 0).unwrap());
+    assert_eq!(token.balance(&fund), params.allocation + fund_share);
+    assert_eq!(token.balance(&rewards), delta - fund_share - burn);
+    assert_eq!(client.burned(), burn);
+    assert_eq!(supply() + client.burned(), params.total_supply);
+
+    let release = client.releases(&0, &1).get(0).unwrap();
+    assert_eq!(release.burn, burn);
+    assert_eq!(release.delta, release.fund + release.rewards + release.burn);
+
+    // The curve is unaffected and the burned amount is not owed again.
+    assert_eq!(client.surplus(), 0);
+    env.ledger().set_sequence_number(LEDGER + 12_000);
+    let delta2 = client.release();
+    assert_eq!(delta + delta2, emission::delta(POOL, params.half_life, origin, origin + 12_000, 0, 0).unwrap());
+    assert_eq!(client.burned(), burn + delta2 * 2_000 / 10_000);
+    assert_eq!(supply() + client.burned(), params.total_supply);
+
+    // The whole rewards share can be burned, rewards keep the rounding
+    // dust only; zero disables the burn.
+    let burn_bps = 10_000 - params.fund_bps;
+    client.set_burn_bps(&burn_bps);
+    env.ledger().set_sequence_number(LEDGER + 15_000);
+    let rewards_balance = token.balance(&rewards);
+    let delta3 = client.release();
+    assert!(token.balance(&rewards) - rewards_balance <= 1);
+    assert_eq!(client.burned(), burn + delta2 * 2_000 / 10_000 + delta3 * burn_bps / 10_000);
+
+    client.set_burn_bps(&0);
+    let burned = client.burned();
+    env.ledger().set_sequence_number(LEDGER + 18_000);
+    client.release();
+    assert_eq!(client.burned(), burned);
+    assert_eq!(supply() + client.burned(), params.total_supply);
+
+    // Tranches of the escrow token burn with the same split; tranches
+    // of another token are outside the burn policy.
+    client.set_burn_bps(&2_000);
+    let partner_id = env.register_stellar_asset_contract_v2(token_admin.clone()).address();
+    let partner = token::Client::new(&env, &partner_id);
+    let ecosystem = Address::generate(&env);
+    let committee = Address::generate(&env);
+    let tranche_origin = origin + 18_000;
+    token::StellarAssetClient::new(&env, &partner_id).mint(&admin, &ECOSYSTEM);
+    client.add_tranche(&1, &token_id, &ecosystem, &committee, &ECOSYSTEM, &tranche_origin, &params.half_life);
+    client.add_tranche(&2, &partner_id, &ecosystem, &committee, &ECOSYSTEM, &tranche_origin, &params.half_life);
+
+    env.ledger().set_sequence_number(LEDGER + 23_000);
+    let delta = client.release_tranche(&1);
+    let fund_share = delta * params.fund_bps / 10_000;
+    let burn = delta * 2_000 / 10_000;
+    let event = TrancheReleased { id: 1, token: token_id.clone(), delta, fund: fund_share,
+        rewards: delta - fund_share - burn, burn };
+    let events = env.events().all();
+    assert_eq!(events.slice(events.len() - 1..),
+        vec![&env, (contract_id.clone(), event.topics(&env), event.data(&env))]);
+    assert_eq!(token.balance(&committee), delta - fund_share - burn);
+    assert_eq!(client.tranche_releases(&1, &0, &1).get(0).unwrap().burn, burn);
+    assert_eq!(client.burned(), burned + burn);
+    assert_eq!(supply() + token.balance(&ecosystem) + token.balance(&committee) + client.burned(),
+        params.total_supply);
+
+    assert_eq!(client.release_tranche(&2), delta);
+    assert_eq!(partner.balance(&committee), delta - fund_share);
+    assert_eq!(client.tranche_releases(&2, &0, &1).get(0).unwrap().burn, 0);
+    assert_eq!(client.burned(), burned + burn);
+}
+
+#[test]
+fn test_ttl_bump() {
+    let params = EmissionParams::default();
+    let Setup { env, client, contract_id, token_id, fund, .. } = setup();
+    let committee = Address::generate(&env);
+    let ttl = || env.as_contract(&contract_id, || env.storage().instance().get_ttl());
+    let max_ttl = env.as_contract(&contract_id, || env.storage().max_ttl());
+    assert_eq!(ttl(), max_ttl);
+
+    // Within the threshold bump is a no-op, past it the TTL is reset.
+    env.ledger().set_sequence_number(LEDGER + storage::TTL_THRESHOLD - 1);
+    client.bump();
+    assert_eq!(ttl(), max_ttl - storage::TTL_THRESHOLD + 1);
+    env.ledger().set_sequence_number(LEDGER + storage::TTL_THRESHOLD);
+    client.bump();
+    assert_eq!(ttl(), max_ttl);
+
+    // History entries are extended on write only; bump_history and
+    // bump_tranche_history extend them past the threshold too.
+    let entry_ttl = |key: Storage| env.as_contract(&contract_id, || env.storage().persistent().get_ttl(&key));
+    let written = env.ledger().sequence();
+    client.add_tranche(&1, &token_id, &fund, &committee, &1_000_000, &(written as u64), &params.half_life);
+    env.ledger().set_sequence_number(written + 1_000);
+    client.release_all();
+    env.ledger().set_sequence_number(written + 1_000 + storage::TTL_THRESHOLD);
+    let decayed = max_ttl - storage::TTL_THRESHOLD;
+    assert_eq!(entry_ttl(Storage::Release(0)), decayed);
+    assert_eq!(entry_ttl(Storage::Checkpoint(1)), decayed);
+    assert_eq!(entry_ttl(Storage::TrancheRelease(1, 0)), decayed);
+    client.bump_history(&0, &10);
+    assert_eq!(entry_ttl(Storage::Release(0)), max_ttl);
+    assert_eq!(entry_ttl(Storage::Checkpoint(0)), max_ttl);
+    assert_eq!(entry_ttl(Storage::Checkpoint(1)), max_ttl);
+    assert_eq!(entry_ttl(Storage::TrancheRelease(1, 0)), decayed);
+    client.bump_tranche_history(&1, &0, &10);
+    assert_eq!(entry_ttl(Storage::TrancheRelease(1, 0)), max_ttl);
+    assert_eq!(client.try_bump_tranche_history(&2, &0, &10), Err(Ok(Error::TrancheNotFound)));
+
+    // Configurable threshold.
+    assert_eq!(client.try_set_ttl_threshold(&(max_ttl + 1)), Err(Ok(Error::InvalidTtl)));
+    client.set_ttl_threshold(&100);
+    let current = env.ledger().sequence();
+    env.ledger().set_sequence_number(current + 100);
+    client.bump();
+    assert_eq!(ttl(), max_ttl);
+}
+
+// Live-until ledger of the instance entry, read from the ledger snapshot:
+// reading it through the contract would restore it.
+fn live_until(env: &Env, contract_id: &Address) -> u32 {
+    let contract = ScAddress::from(contract_id);
+    env.to_ledger_snapshot().ledger_entries.iter()
+        .find_map(|(key, (_, live_until))| match key.as_ref() {
+            LedgerKey::ContractData(data)
+                if data.contract == contract && data.key == ScVal::LedgerKeyContractInstance => *live_until,
+            _ => None,
+        })
+        .unwrap_or_else(|| panic!("instance not found"))
+}
+
+#[test]
+fn test_ttl_archival_restore() {
+    let params = EmissionParams::default();
+    let Setup { env, client, contract_id, .. } = setup();
+    let origin = LEDGER as u64;
+    let max_ttl = env.as_contract(&contract_id, || env.storage().max_ttl());
+
+    // Nobody touches the escrow until the instance is archived. The
+    // test host emulates the protocol 23 automatic restoration of archived
+    // entries in the invoking transaction, and release resumes the curve
+    // with no loss.
+    let target = LEDGER + max_ttl + 1;
+    env.ledger().set_sequence_number(target);
+    assert!(live_until(&env, &contract_id) < target);
+    assert_eq!(client.release(), emission::delta(POOL, params.half_life, origin, target as u64, 0, 0).unwrap());
+    let ttl = env.as_contract(&contract_id, || env.storage().instance().get_ttl());
+    assert_eq!(ttl, max_ttl);
+}
+
+#[test]
+fn test_migrate_from_1_0_0() {
+    let params = EmissionParams::default();
+    let legacy = include_bytes!("../fixtures/escrow-1.0.0.wasm");
+    let Setup { env, client, contract_id, token_id, token, token_admin_client, admin, fund, rewards,
+        .. } = deploy(&legacy[..]);
+    let origin = LEDGER as u64;
     let wasm = include_bytes!("../../../target/wasm32-unknown-unknown/release/escrow.wasm");
     let wasm_hash = env.deployer().upload_contract_wasm(Bytes::from_slice(&env, wasm));
 
-    env.mock_all_auths();
-    env.ledger().set_sequence_number(start_ledger);
-    token_admin_client.mint(&admin, &params.total_supply);
-    client.initialize(&admin, &token_id, &fund, &rewards, &start_ledger);
+    client.initialize(&admin, &token_id, &fund, &rewards, &LEDGER);
+
+    // Releases under the 1.0.0 layout, individual keys and no history.
+    env.ledger().set_sequence_number(LEDGER + 5_000);
+    let r0 = client.release();
+    assert!(r0 > 0);
+
     client.upgrade(&wasm_hash);
-    env.ledger().set_sequence_number(start_ledger + 1_000);
-    assert!(client.release() >= 0);
+    assert_eq!(client.version(), 1);
+    client.migrate();
+    assert_eq!(client.version(), storage::VERSION);
+    assert_eq!(client.try_migrate(), Err(Ok(Error::AlreadyMigrated)));
+
+    // Emission before the upgrade is anchored by a checkpoint; history
+    // starts with the first release after it.
+    assert_eq!(client.release(), 0);
+    assert_eq!(client.emitted_at(&(origin + 5_000)), r0);
+    assert_eq!(client.release_count(), 0);
+    assert_eq!(client.half_life(), params.half_life);
+    assert_eq!(client.burn_bps(), 0);
+    assert_eq!(client.surplus(), 0);
+    env.ledger().set_sequence_number(LEDGER + 12_000);
+    let r1 = client.release();
+    assert_eq!(r0 + r1, emission::delta(POOL, params.half_life, origin, origin + 12_000, 0, 0).unwrap());
+    assert_eq!(token.balance(&fund) + token.balance(&rewards), params.allocation + r0 + r1);
+    assert_eq!(client.release_count(), 1);
+    assert_eq!(client.releases(&0, &10).get(0).unwrap().cumulative, r0 + r1);
+
+    // Entrypoints added since 1.0.0 work on the migrated layout.
+    client.pause();
+    env.ledger().set_sequence_number(LEDGER + 20_000);
+    client.unpause();
+    assert_eq!(client.release(), 0);
+    assert_eq!(client.emitted_at(&(origin + 20_000)), r0 + r1);
+    client.set_burn_bps(&2_000);
+    env.ledger().set_sequence_number(LEDGER + 25_000);
+    let r2 = client.release();
+    assert_eq!(client.burned(), r2 * 2_000 / 10_000);
+    token_admin_client.mint(&contract_id, &1_000);
+    let fund_balance = token.balance(&fund);
+    assert_eq!(client.reconcile(), 1_000);
+    assert_eq!(token.balance(&fund), fund_balance + 1_000);
+    assert_eq!(token.balance(&contract_id), client.locked(&token_id));
 }
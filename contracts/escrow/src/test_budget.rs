@@ -0,0 +1,138 @@
+/*
+    Copyright (c) 2026 XBID LABS LLC
+
+    This file is part of XBID-AI project.
+    Licensed under the MIT License.
+    Author: Fred Kyung-jin Rezeau (오경진 吳景振) <hello@kyungj.in>
+*/
+
+// Resource baselines of the wasm build, per entrypoint. A change that
+// raises any cost above its baseline fails here; lower the baseline
+// when an optimization lands so regressions stay visible. The test
+// builds the release wasm itself, so it always measures the current
+// sources.
+
+extern crate std;
+
+use crate::test::{deploy, Setup, LEDGER};
+use crate::types::EmissionParams;
+use std::{fs, path::Path, process::Command, vec::Vec};
+use soroban_sdk::{testutils::Ledger, token, Bytes, Env};
+
+#[derive(Debug)]
+struct Cost {
+    cpu: u64,
+    mem: u64,
+    read_entries: u32,
+    write_entries: u32,
+    write_bytes: u32,
+}
+
+// (entrypoint, cpu, mem, read entries, write entries, write bytes)
+const BASELINES: &[(&str, u64, u64, u32, u32, u32)] = &[
+    ("initialize",      1_310_000, 1_350_000, 9, 5, 1_948),
+    ("release_first",   1_520_000, 1_360_000, 9, 6, 2_440),
+    ("release_steady",  1_540_000, 1_360_000, 8, 6, 2_440),
+    ("release_gap",     1_570_000, 1_380_000, 8, 7, 2_908),
+    ("release_empty",     760_000, 1_290_000, 2, 0, 0),
+    ("reconcile",       1_070_000, 1_330_000, 5, 2, 448),
+    ("top_up",          1_260_000, 1_340_000, 8, 5, 1_952),
+    ("set_half_life",   1_130_000, 1_320_000, 5, 3, 1_512),
+    ("sweep",           1_070_000, 1_330_000, 6, 2, 448),
+    ("add_tranche",     1_530_000, 1_370_000, 7, 3, 1_960),
+    ("release_tranche", 1_540_000, 1_380_000, 7, 5, 2_692),
+    ("release_token",   2_650_000, 1_460_000, 9, 7, 3_248),
+    ("release_all",     2_650_000, 1_470_000, 9, 7, 3_248),
+    ("upgrade",           950_000, 1_330_000, 4, 2, 1_740),
+];
+
+// Release build of the current sources, as `make build_cargo` does. It
+// goes to its own target dir, the workspace one is locked by cargo test.
+fn build() -> Vec<u8> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let target = dir.join("../../target/budget");
+    let status = Command::new(env!("CARGO"))
+        .args(["build", "--target", "wasm32v1-none", "--release", "-p", "escrow", "--target-dir"])
+        .arg(&target)
+        .current_dir(dir)
+        .status()
+        .expect("cargo not found");
+    assert!(status.success(), "escrow wasm build failed, run `rustup target add wasm32v1-none`");
+    fs::read(target.join("wasm32v1-none/release/escrow.wasm")).expect("escrow.wasm not found")
+}
+
+fn measure<T>(env: &Env, call: impl FnOnce() -> T) -> Cost {
+    env.cost_estimate().budget().reset_default();
+    call();
+    let budget = env.cost_estimate().budget();
+    let resources = env.cost_estimate().resources();
+    Cost {
+        cpu: budget.cpu_instruction_cost(),
+        mem: budget.memory_bytes_cost(),
+        read_entries: resources.disk_read_entries + resources.memory_read_entries,
+        write_entries: resources.write_entries,
+        write_bytes: resources.write_bytes,
+    }
+}
+
+fn check(name: &str, cost: Cost) {
+    let (_, cpu, mem, read_entries, write_entries, write_bytes) = BASELINES.iter()
+        .find(|baseline| baseline.0 == name)
+        .unwrap_or_else(|| panic!("no baseline for {}", name));
+    assert!(cost.cpu <= *cpu, "{} cpu above baseline: {:?}", name, cost);
+    assert!(cost.mem <= *mem, "{} memory above baseline: {:?}", name, cost);
+    assert!(cost.read_entries <= *read_entries, "{} reads above baseline: {:?}", name, cost);
+    assert!(cost.write_entries <= *write_entries, "{} writes above baseline: {:?}", name, cost);
+    assert!(cost.write_bytes <= *write_bytes, "{} write bytes above baseline: {:?}", name, cost);
+}
+
+#[test]
+fn test_budget() {
+    let wasm = build();
+    let params = EmissionParams::default();
+    let Setup { env, client, contract_id, token_admin, token_id, token_admin_client, admin, fund, rewards,
+        .. } = deploy(wasm.as_slice());
+    let wasm_hash = env.deployer().upload_contract_wasm(Bytes::from_slice(&env, &wasm));
+
+    check("initialize", measure(&env,
+        || client.initialize(&admin, &token_id, &fund, &rewards, &LEDGER)));
+
+    env.ledger().set_sequence_number(LEDGER + 100);
+    check("release_first", measure(&env, || client.release()));
+
+    env.ledger().set_sequence_number(LEDGER + 200);
+    check("release_steady", measure(&env, || client.release()));
+
+    env.ledger().set_sequence_number(LEDGER + 200 + 6_307_200);
+    check("release_gap", measure(&env, || client.release()));
+
+    check("release_empty", measure(&env, || client.release()));
+
+    token_admin_client.mint(&contract_id, &1_000);
+    check("reconcile", measure(&env, || client.reconcile()));
+
+    token_admin_client.mint(&admin, &params.min_top_up);
+    check("top_up", measure(&env, || client.top_up(&admin, &params.min_top_up)));
+
+    let half_life = params.half_life + params.half_life / 10;
+    check("set_half_life", measure(&env, || client.set_half_life(&half_life)));
+
+    let stray_id = env.register_stellar_asset_contract_v2(token_admin.clone()).address();
+    token::StellarAssetClient::new(&env, &stray_id).mint(&contract_id, &1_000);
+    check("sweep", measure(&env, || client.sweep(&stray_id)));
+
+    let now = env.ledger().sequence() as u64;
+    check("add_tranche", measure(&env, || client.add_tranche(&1, &token_id, &fund, &rewards,
+        &params.allocation, &now, &params.half_life)));
+
+    env.ledger().set_sequence_number(now as u32 + 100);
+    check("release_tranche", measure(&env, || client.release_tranche(&1)));
+
+    env.ledger().set_sequence_number(now as u32 + 200);
+    check("release_token", measure(&env, || client.release_token(&token_id)));
+
+    env.ledger().set_sequence_number(now as u32 + 300);
+    check("release_all", measure(&env, || client.release_all()));
+
+    check("upgrade", measure(&env, || client.upgrade(&wasm_hash)));
+}
@@ -0,0 +1,86 @@
+/*
+    Copyright (c) 2026 XBID LABS LLC
+
+    This file is part of XBID-AI project.
+    Licensed under the MIT License.
+    Author: Fred Kyung-jin Rezeau (오경진 吳景振) <hello@kyungj.in>
+*/
+
+// Property tests over random release sequences: whatever the spacing
+// of calls, the escrow pays E(t), the split keeps its share and the
+// supply is conserved once burns are counted.
+
+extern crate std;
+
+use crate::*;
+use crate::emission;
+use crate::test::{deploy, Setup, LEDGER};
+use crate::types::EmissionParams;
+use proptest::prelude::*;
+use soroban_sdk::testutils::Ledger;
+use std::vec::Vec;
+
+// Gaps between releases, in clock units: bursts of adjacent calls,
+// typical spacing and multi-year silences.
+fn gaps() -> impl Strategy<Value = Vec<u64>> {
+    prop::collection::vec(prop_oneof![0..10u64, 10..100_000u64, 100_000..20_000_000u64], 1..24)
+}
+
+fn run(timestamp: bool, burn_bps: i128, gaps: Vec<u64>) -> Result<(), TestCaseError> {
+    let params = if timestamp { EmissionParams::timestamp() } else { EmissionParams::default() };
+    let Setup { env, client, contract_id, token, token_id, admin, fund, rewards, .. } = deploy(Escrow);
+    let origin = if timestamp { 1_700_000_000u64 } else { LEDGER as u64 };
+
+    env.ledger().set_timestamp(1_700_000_000);
+    if timestamp {
+        client.initialize_timestamp(&admin, &token_id, &fund, &rewards, &origin);
+    } else {
+        client.initialize(&admin, &token_id, &fund, &rewards, &(origin as u32));
+    }
+    client.set_burn_bps(&burn_bps);
+
+    let mut now = origin;
+    let mut released = 0i128;
+    for (releases, gap) in gaps.iter().enumerate() {
+        now += gap;
+        if timestamp {
+            env.ledger().set_timestamp(now);
+        } else {
+            env.ledger().set_sequence_number(now as u32);
+        }
+        released += client.release();
+
+        // Total emitted follows the curve at the last release.
+        let fund_balance = token.balance(&fund) - params.allocation;
+        let rewards_balance = token.balance(&rewards);
+        let expected = emission::delta(params.pool(), params.half_life, origin, now, 0, 0).unwrap();
+        prop_assert_eq!(released, expected);
+        prop_assert_eq!(fund_balance + rewards_balance + client.burned(), expected);
+
+        // Nothing minted or lost: what left the supply was burned.
+        let escrow_balance = token.balance(&contract_id);
+        prop_assert_eq!(token.balance(&fund) + rewards_balance + escrow_balance + client.burned(),
+            params.total_supply);
+
+        // Each release floors its fund share: at most one stroop short per call.
+        let share = expected * params.fund_bps / 10_000;
+        prop_assert!(fund_balance <= share);
+        prop_assert!(share - fund_balance <= releases as i128 + 1);
+    }
+
+    Ok(())
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(32))]
+
+    #[test]
+    fn test_props_ledger(burn_bps in 0..=9_900i128, gaps in gaps()) {
+        run(false, burn_bps, gaps)?;
+    }
+
+    #[test]
+    fn test_props_timestamp(burn_bps in 0..=9_900i128, gaps in gaps()) {
+        run(true, burn_bps, gaps)?;
+    }
+}
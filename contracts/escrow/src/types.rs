@@ -6,7 +6,7 @@
     Author: Fred Kyung-jin Rezeau (오경진 吳景振) <hello@kyungj.in>
 */
 
-use soroban_sdk::{contracterror, contracttype};
+use soroban_sdk::{contracterror, contractevent, contracttype, Address};
 
 #[derive(Clone, Copy)]
 pub struct EmissionParams {
@@ -14,6 +14,11 @@ pub struct EmissionParams {
     pub allocation: i128,
     pub fund_bps: i128,
     pub half_life: i128,
+    pub origin_tolerance: u64,
+    pub origin_horizon: u64,
+    pub half_life_step_bps: i128,
+    pub retune_period: u64,
+    pub min_top_up: i128,
 }
 
 impl EmissionParams {
@@ -23,6 +28,29 @@ impl EmissionParams {
             allocation: 200_000_001_400_000,
             fund_bps: 100,
             half_life: 7 * 6_307_200,
+            origin_tolerance: 17_280,
+            origin_horizon: 6_307_200,
+            half_life_step_bps: 2_500,
+            retune_period: 6_307_200,
+            min_top_up: 10_000_000_000,
+        }
+    }
+
+    // Same schedule with the half-life expressed in seconds.
+    pub const fn timestamp() -> Self {
+        Self {
+            half_life: 7 * 31_536_000,
+            origin_tolerance: 86_400,
+            origin_horizon: 31_536_000,
+            retune_period: 31_536_000,
+            ..Self::default()
+        }
+    }
+
+    pub const fn of(clock: Clock) -> Self {
+        match clock {
+            Clock::Ledger => Self::default(),
+            Clock::Timestamp => Self::timestamp(),
         }
     }
 
@@ -31,18 +59,188 @@ impl EmissionParams {
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[contracttype]
+pub enum Clock {
+    Ledger,
+    Timestamp,
+}
+
+// Nothing is emitted before origin + duration. At the cliff the curve
+// either catches up from origin or starts fresh from the cliff.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[contracttype]
+pub struct Cliff {
+    pub duration: u64,
+    pub catch_up: bool,
+}
+
+impl Cliff {
+    pub const NONE: Self = Self { duration: 0, catch_up: true };
+}
+
+// Upper bound on a single release, as an absolute amount or as the
+// curve emission over the trailing duration (clock units).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[contracttype]
+pub enum ReleaseCap {
+    Amount(i128),
+    Duration(u64),
+}
+
+impl ReleaseCap {
+    pub const NONE: Self = Self::Amount(i128::MAX);
+}
+
+// Releases below min_delta, or within min_gap (clock units) of the
+// previous release, pay nothing and leave the accrual in place.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[contracttype]
+pub struct ReleaseThreshold {
+    pub min_delta: i128,
+    pub min_gap: u64,
+}
+
+impl ReleaseThreshold {
+    pub const NONE: Self = Self { min_delta: 0, min_gap: 0 };
+}
+
+// What reconcile does with escrow-token balance above the undistributed
+// pool: pay it to the fund, to rewards, or grow the curve pool.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[contracttype]
+pub enum SurplusPolicy {
+    Fund,
+    Rewards,
+    TopUp,
+}
+
+// Configuration and mutable state read by every release, packed
+// into a single instance entry. Unset cliff, cap and threshold are
+// stored as their NONE values. Rebases (top-ups, half-life changes)
+// move the curve position by `shift` clock units; `carry` is the
+// emission that position rounds away, added back. `burn_bps` of each
+// release is burned, taken from the rewards share.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[contracttype]
+pub struct State {
+    pub token: Address,
+    pub fund: Address,
+    pub rewards: Address,
+    pub clock: Clock,
+    pub origin: u64,
+    pub pool: i128,
+    pub half_life: i128,
+    pub shift: i128,
+    pub carry: i128,
+    pub retuned_at: Option<u64>,
+    pub cliff: Cliff,
+    pub cap: ReleaseCap,
+    pub threshold: ReleaseThreshold,
+    pub surplus: SurplusPolicy,
+    pub burn_bps: i128,
+    pub ttl_threshold: u32,
+    pub paused: u64,
+    pub paused_at: Option<u64>,
+    pub last_release: Option<u64>,
+    pub emitted: i128,
+    pub burned: i128,
+    pub releases: u32,
+    pub checkpoints: u32,
+}
+
+// An independent emission program sharing the escrow clock: its own
+// token, pool, origin, half-life, recipients and release history.
+// `paused` is the escrow pause total when the tranche was added.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[contracttype]
+pub struct Tranche {
+    pub token: Address,
+    pub fund: Address,
+    pub rewards: Address,
+    pub origin: u64,
+    pub pool: i128,
+    pub half_life: i128,
+    pub paused: u64,
+    pub emitted: i128,
+    pub releases: u32,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[contractevent]
+pub struct TrancheReleased {
+    #[topic]
+    pub id: u32,
+    pub token: Address,
+    pub delta: i128,
+    pub fund: i128,
+    pub rewards: i128,
+    pub burn: i128,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[contracttype]
+pub struct Release {
+    pub ledger: u32,
+    pub timestamp: u64,
+    pub delta: i128,
+    pub fund: i128,
+    pub rewards: i128,
+    pub burn: i128,
+    pub cumulative: i128,
+}
+
+// Cumulative emission at a release or pause transition (clock units),
+// with the completed paused duration and whether a pause is ongoing.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[contracttype]
+pub struct Checkpoint {
+    pub at: u64,
+    pub paused: u64,
+    pub halted: bool,
+    pub emitted: i128,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 #[contracterror]
 pub enum Error {
     AlreadyInitialized = 1,
     InvalidAddress = 2,
     InvalidSupply = 3,
+    AlreadyPaused = 4,
+    NotPaused = 5,
+    OriginTooEarly = 6,
+    OriginTooLate = 7,
+    AlreadyStarted = 8,
+    InvalidCliff = 9,
+    InvalidCap = 10,
+    InvalidThreshold = 11,
+    InvalidTtl = 12,
+    AlreadyMigrated = 13,
+    ArithmeticOverflow = 14,
+    InvalidToken = 15,
+    InvalidAmount = 16,
+    InvalidHalfLife = 17,
+    HalfLifeLocked = 18,
+    TrancheExists = 19,
+    TrancheNotFound = 20,
+    TooManyTranches = 21,
+    InvalidTranche = 22,
+    InvalidBurn = 23,
 }
 
 #[derive(Clone, Copy)]
 #[contracttype]
 pub enum Storage {
     Admin,
+    State,
+    Release(u32),
+    Checkpoint(u32),
+    Version,
+    Tranches,
+    Tranche(u32),
+    TrancheRelease(u32, u32),
+    // 1.0.0 layout, read by migrate only.
     Token,
     Fund,
     Rewards,
@@ -0,0 +1,130 @@
+/*
+    Copyright (c) 2026 XBID LABS LLC
+
+    This file is part of XBID-AI project.
+    Licensed under the MIT License.
+    Author: Fred Kyung-jin Rezeau (오경진 吳景振) <hello@kyungj.in>
+*/
+
+use crate::storage;
+use crate::types::{Clock, Deployed, Deployment, Error, EscrowConfig, WasmUpdated};
+use soroban_sdk::{contractclient, xdr::ToXdr, Address, BytesN, Env, Vec};
+
+const MAX_PAGE: u32 = 100;
+
+// Escrow entrypoints called by the factory; the trait itself only
+// generates the client.
+#[allow(dead_code)]
+#[contractclient(name = "EscrowClient")]
+pub trait EscrowInterface {
+    fn initialize(env: Env, admin: Address, token: Address,
+        fund: Address, rewards: Address, ledger: u32);
+    fn initialize_timestamp(env: Env, admin: Address, token: Address,
+        fund: Address, rewards: Address, timestamp: u64);
+}
+
+pub fn initialize(env: &Env, admin: Address, wasm_hash: BytesN<32>) -> Result<(), Error> {
+    if storage::has_admin(env) {
+        return Err(Error::AlreadyInitialized);
+    }
+
+    admin.require_auth();
+
+    storage::set_admin(env, &admin);
+    storage::set_wasm_hash(env, &wasm_hash);
+    storage::extend_ttl(env);
+
+    Ok(())
+}
+
+// Point new deployments at another audited build. Admin only; escrows
+// already deployed are unaffected.
+pub fn set_wasm_hash(env: &Env, wasm_hash: BytesN<32>) {
+    storage::get_admin(env).require_auth();
+    storage::set_wasm_hash(env, &wasm_hash);
+    storage::extend_ttl(env);
+
+    WasmUpdated { wasm_hash }.publish(env);
+}
+
+pub fn wasm_hash(env: &Env) -> BytesN<32> {
+    storage::get_wasm_hash(env)
+}
+
+// Deploy and initialize an escrow in one call. The address depends on
+// the deployer and salt only, see `address`.
+pub fn deploy(env: &Env, deployer: Address, salt: BytesN<32>,
+    config: EscrowConfig) -> Result<Address, Error> {
+    deployer.require_auth();
+
+    let escrow = address(env, deployer.clone(), salt.clone());
+    if storage::get_deployment(env, &escrow).is_some() {
+        return Err(Error::AlreadyDeployed);
+    }
+
+    let wasm_hash = storage::get_wasm_hash(env);
+    env.deployer()
+        .with_current_contract(scoped(env, &deployer, &salt))
+        .deploy_v2(wasm_hash.clone(), ());
+
+    let client = EscrowClient::new(env, &escrow);
+    match config.clock {
+        Clock::Ledger => {
+            let ledger = u32::try_from(config.origin).map_err(|_| Error::InvalidOrigin)?;
+            client.initialize(&config.admin, &config.token, &config.fund, &config.rewards, &ledger);
+        }
+        Clock::Timestamp => client.initialize_timestamp(&config.admin, &config.token,
+            &config.fund, &config.rewards, &config.origin),
+    }
+
+    let index = storage::get_count(env);
+    storage::set_index(env, index, &escrow);
+    storage::set_deployment(env, &escrow, &Deployment {
+        index,
+        deployer: deployer.clone(),
+        salt,
+        wasm_hash: wasm_hash.clone(),
+        config,
+        ledger: env.ledger().sequence(),
+    });
+    storage::set_count(env, index + 1);
+    storage::extend_ttl(env);
+
+    Deployed { deployer, escrow: escrow.clone(), index, wasm_hash }.publish(env);
+
+    Ok(escrow)
+}
+
+// Address `deploy` yields for (deployer, salt), deployed or not.
+pub fn address(env: &Env, deployer: Address, salt: BytesN<32>) -> Address {
+    env.deployer()
+        .with_current_contract(scoped(env, &deployer, &salt))
+        .deployed_address()
+}
+
+pub fn escrow(env: &Env, escrow: Address) -> Result<Deployment, Error> {
+    storage::get_deployment(env, &escrow).ok_or(Error::NotFound)
+}
+
+pub fn count(env: &Env) -> u32 {
+    storage::get_count(env)
+}
+
+pub fn escrows(env: &Env, from: u32, limit: u32) -> Vec<Address> {
+    let end = storage::get_count(env)
+        .min(from.saturating_add(limit.min(MAX_PAGE)));
+    let mut page = Vec::new(env);
+    for index in from..end {
+        page.push_back(storage::get_index(env, index));
+    }
+
+    page
+}
+
+// Salts are scoped to the deployer so nobody can take another
+// deployer's address.
+fn scoped(env: &Env, deployer: &Address, salt: &BytesN<32>) -> BytesN<32> {
+    let mut bytes = deployer.clone().to_xdr(env);
+    bytes.append(&salt.clone().into());
+    env.crypto().sha256(&bytes).into()
+}
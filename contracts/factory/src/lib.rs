@@ -0,0 +1,60 @@
+/*
+    Copyright (c) 2026 XBID LABS LLC
+
+    This file is part of XBID-AI project.
+    Licensed under the MIT License.
+    Author: Fred Kyung-jin Rezeau (오경진 吳景振) <hello@kyungj.in>
+*/
+
+#![no_std]
+
+mod factory;
+mod storage;
+mod types;
+
+use soroban_sdk::{contract, contractimpl, Address, BytesN, Env, Vec};
+use types::Error;
+
+pub use types::{Clock, Deployed, Deployment, EscrowConfig, WasmUpdated};
+
+#[contract]
+pub struct Factory;
+
+#[contractimpl]
+impl Factory {
+    pub fn initialize(env: Env, admin: Address, wasm_hash: BytesN<32>) -> Result<(), Error> {
+        factory::initialize(&env, admin, wasm_hash)
+    }
+
+    pub fn set_wasm_hash(env: Env, wasm_hash: BytesN<32>) {
+        factory::set_wasm_hash(&env, wasm_hash)
+    }
+
+    pub fn wasm_hash(env: Env) -> BytesN<32> {
+        factory::wasm_hash(&env)
+    }
+
+    pub fn deploy(env: Env, deployer: Address, salt: BytesN<32>,
+        config: EscrowConfig) -> Result<Address, Error> {
+        factory::deploy(&env, deployer, salt, config)
+    }
+
+    pub fn address(env: Env, deployer: Address, salt: BytesN<32>) -> Address {
+        factory::address(&env, deployer, salt)
+    }
+
+    pub fn escrow(env: Env, escrow: Address) -> Result<Deployment, Error> {
+        factory::escrow(&env, escrow)
+    }
+
+    pub fn count(env: Env) -> u32 {
+        factory::count(&env)
+    }
+
+    pub fn escrows(env: Env, from: u32, limit: u32) -> Vec<Address> {
+        factory::escrows(&env, from, limit)
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,84 @@
+/*
+    Copyright (c) 2026 XBID LABS LLC
+
+    This file is part of XBID-AI project.
+    Licensed under the MIT License.
+    Author: Fred Kyung-jin Rezeau (오경진 吳景振) <hello@kyungj.in>
+*/
+
+use crate::types::{Deployment, Storage};
+use soroban_sdk::{Address, BytesN, Env};
+
+// Fixed: the factory instance is re-extended on writes once it has
+// decayed a week of ledgers. Registry entries are written at max TTL.
+pub const TTL_THRESHOLD: u32 = 120_960;
+
+pub fn has_admin(env: &Env) -> bool {
+    env.storage().instance().has(&Storage::Admin)
+}
+
+pub fn get_admin(env: &Env) -> Address {
+    env.storage().instance()
+        .get::<Storage, Address>(&Storage::Admin)
+        .unwrap_or_else(|| panic!("admin not set"))
+}
+
+pub fn set_admin(env: &Env, admin: &Address) {
+    env.storage().instance()
+        .set::<Storage, Address>(&Storage::Admin, admin);
+}
+
+pub fn get_wasm_hash(env: &Env) -> BytesN<32> {
+    env.storage().instance()
+        .get::<Storage, BytesN<32>>(&Storage::WasmHash)
+        .unwrap_or_else(|| panic!("wasm hash not set"))
+}
+
+pub fn set_wasm_hash(env: &Env, hash: &BytesN<32>) {
+    env.storage().instance()
+        .set::<Storage, BytesN<32>>(&Storage::WasmHash, hash);
+}
+
+pub fn get_count(env: &Env) -> u32 {
+    env.storage().instance()
+        .get::<Storage, u32>(&Storage::Count)
+        .unwrap_or(0)
+}
+
+pub fn set_count(env: &Env, count: u32) {
+    env.storage().instance()
+        .set::<Storage, u32>(&Storage::Count, &count);
+}
+
+pub fn get_index(env: &Env, index: u32) -> Address {
+    env.storage().persistent()
+        .get::<Storage, Address>(&Storage::Index(index))
+        .unwrap_or_else(|| panic!("index not set"))
+}
+
+pub fn set_index(env: &Env, index: u32, escrow: &Address) {
+    let key = Storage::Index(index);
+    env.storage().persistent()
+        .set::<Storage, Address>(&key, escrow);
+    let max_ttl = env.storage().max_ttl();
+    env.storage().persistent().extend_ttl(&key, max_ttl, max_ttl);
+}
+
+pub fn get_deployment(env: &Env, escrow: &Address) -> Option<Deployment> {
+    env.storage().persistent()
+        .get::<Storage, Deployment>(&Storage::Escrow(escrow.clone()))
+}
+
+pub fn set_deployment(env: &Env, escrow: &Address, deployment: &Deployment) {
+    let key = Storage::Escrow(escrow.clone());
+    env.storage().persistent()
+        .set::<Storage, Deployment>(&key, deployment);
+    let max_ttl = env.storage().max_ttl();
+    env.storage().persistent().extend_ttl(&key, max_ttl, max_ttl);
+}
+
+pub fn extend_ttl(env: &Env) {
+    let max_ttl = env.storage().max_ttl();
+    let threshold = max_ttl.saturating_sub(TTL_THRESHOLD);
+    env.storage().instance().extend_ttl(threshold, max_ttl);
+}
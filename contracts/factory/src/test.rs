@@ -0,0 +1,187 @@
+/*
+    Copyright (c) 2026 XBID LABS LLC
+
+    This file is part of XBID-AI project.
+    Licensed under the MIT License.
+    Author: Fred Kyung-jin Rezeau (오경진 吳景振) <hello@kyungj.in>
+*/
+
+use crate::*;
+use ::escrow::EmissionParams;
+use soroban_sdk::{testutils::{Address as _, Events, Ledger}, token, Address, Bytes, BytesN, Env, Event,
+    vec, Vec};
+
+#[allow(clippy::too_many_arguments)]
+mod escrow {
+    soroban_sdk::contractimport!(file = "../../target/wasm32-unknown-unknown/release/escrow.wasm");
+}
+
+const TOTAL_SUPPLY: i128 = EmissionParams::default().total_supply;
+const ALLOCATION: i128 = EmissionParams::default().allocation;
+const LEDGER: u32 = 1_000;
+
+struct Setup {
+    env: Env,
+    client: FactoryClient<'static>,
+    factory_id: Address,
+    token_id: Address,
+    token: token::Client<'static>,
+    token_admin_client: token::StellarAssetClient<'static>,
+    owner: Address,
+    deployer: Address,
+    admin: Address,
+    fund: Address,
+    rewards: Address,
+    wasm_hash: BytesN<32>,
+}
+
+// A factory, not initialized, next to a fresh SAC and the uploaded
+// escrow wasm, at ledger LEDGER with admin holding the total supply.
+fn setup() -> Setup {
+    let env = Env::default();
+    let factory_id = env.register(Factory, ());
+    let client = FactoryClient::new(&env, &factory_id);
+    let token_admin = Address::generate(&env);
+    let token_id = env.register_stellar_asset_contract_v2(token_admin).address();
+    let token = token::Client::new(&env, &token_id);
+    let token_admin_client = token::StellarAssetClient::new(&env, &token_id);
+    let owner = Address::generate(&env);
+    let deployer = Address::generate(&env);
+    let admin = Address::generate(&env);
+    let fund = Address::generate(&env);
+    let rewards = Address::generate(&env);
+    let wasm_hash = env.deployer().upload_contract_wasm(Bytes::from_slice(&env, escrow::WASM));
+
+    env.mock_all_auths_allowing_non_root_auth();
+    env.ledger().set_sequence_number(LEDGER);
+    token_admin_client.mint(&admin, &TOTAL_SUPPLY);
+
+    Setup { env, client, factory_id, token_id, token, token_admin_client, owner, deployer, admin, fund,
+        rewards, wasm_hash }
+}
+
+#[test]
+fn test_deploy() {
+    let Setup { env, client, factory_id, token_id, token, owner, deployer, admin, fund, rewards, wasm_hash,
+        .. } = setup();
+    let salt = BytesN::from_array(&env, &[1; 32]);
+
+    client.initialize(&owner, &wasm_hash);
+    assert_eq!(client.try_initialize(&owner, &wasm_hash), Err(Ok(Error::AlreadyInitialized)));
+
+    let config = EscrowConfig {
+        admin: admin.clone(),
+        token: token_id.clone(),
+        fund: fund.clone(),
+        rewards: rewards.clone(),
+        clock: Clock::Ledger,
+        origin: LEDGER as u64,
+    };
+
+    // Deterministic address, known before deployment.
+    let expected = client.address(&deployer, &salt);
+    let escrow_id = client.deploy(&deployer, &salt, &config);
+    assert_eq!(escrow_id, expected);
+
+    let event = Deployed { deployer: deployer.clone(), escrow: escrow_id.clone(), index: 0,
+        wasm_hash: wasm_hash.clone() };
+    let events = env.events().all();
+    assert_eq!(events.slice(events.len() - 1..),
+        vec![&env, (factory_id.clone(), event.topics(&env), event.data(&env))]);
+
+    // Initialized in the same call.
+    let escrow = escrow::Client::new(&env, &escrow_id);
+    assert_eq!(token.balance(&escrow_id), TOTAL_SUPPLY - ALLOCATION);
+    assert_eq!(token.balance(&fund), ALLOCATION);
+    env.ledger().set_sequence_number(LEDGER + 1_000);
+    assert!(escrow.release() > 0);
+
+    // Registered with its params.
+    let deployment = client.escrow(&escrow_id);
+    assert_eq!(deployment.index, 0);
+    assert_eq!(deployment.deployer, deployer);
+    assert_eq!(deployment.salt, salt);
+    assert_eq!(deployment.wasm_hash, wasm_hash);
+    assert_eq!(deployment.config, config);
+    assert_eq!(deployment.ledger, LEDGER);
+    assert_eq!(client.count(), 1);
+    assert_eq!(client.escrows(&0, &10), vec![&env, escrow_id.clone()]);
+    assert_eq!(client.try_escrow(&Address::generate(&env)), Err(Ok(Error::NotFound)));
+
+    // Salts are scoped per deployer.
+    assert_eq!(client.try_deploy(&deployer, &salt, &config), Err(Ok(Error::AlreadyDeployed)));
+    assert_ne!(client.address(&Address::generate(&env), &salt), escrow_id);
+}
+
+#[test]
+fn test_deploy_timestamp() {
+    let Setup { env, client, token_id, owner, deployer, admin, fund, rewards, wasm_hash, .. } = setup();
+    let timestamp: u64 = 1_800_000_000;
+
+    env.ledger().set_timestamp(timestamp);
+    client.initialize(&owner, &wasm_hash);
+
+    let mut config = EscrowConfig {
+        admin: admin.clone(),
+        token: token_id.clone(),
+        fund: fund.clone(),
+        rewards: rewards.clone(),
+        clock: Clock::Ledger,
+        origin: u32::MAX as u64 + 1,
+    };
+
+    // Ledger origins must fit a ledger sequence.
+    let salt = BytesN::from_array(&env, &[2; 32]);
+    assert_eq!(client.try_deploy(&deployer, &salt, &config), Err(Ok(Error::InvalidOrigin)));
+    assert_eq!(client.count(), 0);
+
+    config.clock = Clock::Timestamp;
+    config.origin = timestamp;
+    let escrow_id = client.deploy(&deployer, &salt, &config);
+    let escrow = escrow::Client::new(&env, &escrow_id);
+    env.ledger().set_timestamp(timestamp + 86_400);
+    assert!(escrow.release() > 0);
+    assert_eq!(client.escrow(&escrow_id).config.clock, Clock::Timestamp);
+}
+
+#[test]
+fn test_set_wasm_hash() {
+    let Setup { env, client, factory_id, token_id, token_admin_client, owner, deployer, wasm_hash, .. } = setup();
+    let unknown = BytesN::from_array(&env, &[9; 32]);
+
+    client.initialize(&owner, &unknown);
+
+    // Owner only.
+    client.set_wasm_hash(&wasm_hash);
+    assert_eq!(env.auths()[0].0, owner);
+    let event = WasmUpdated { wasm_hash: wasm_hash.clone() };
+    let events = env.events().all();
+    assert_eq!(events.slice(events.len() - 1..),
+        vec![&env, (factory_id.clone(), event.topics(&env), event.data(&env))]);
+    assert_eq!(client.wasm_hash(), wasm_hash);
+
+    // Deployments follow the current hash and record it.
+    let mut escrows = Vec::new(&env);
+    for i in 0..3u8 {
+        let admin = Address::generate(&env);
+        token_admin_client.mint(&admin, &TOTAL_SUPPLY);
+        let config = EscrowConfig {
+            admin,
+            token: token_id.clone(),
+            fund: Address::generate(&env),
+            rewards: Address::generate(&env),
+            clock: Clock::Ledger,
+            origin: LEDGER as u64,
+        };
+
+        let escrow_id = client.deploy(&deployer, &BytesN::from_array(&env, &[i; 32]), &config);
+        assert_eq!(client.escrow(&escrow_id).wasm_hash, wasm_hash);
+        assert_eq!(client.escrow(&escrow_id).index, i as u32);
+        escrows.push_back(escrow_id);
+    }
+
+    assert_eq!(client.count(), 3);
+    assert_eq!(client.escrows(&0, &10), escrows);
+    assert_eq!(client.escrows(&1, &1), escrows.slice(1..2));
+    assert_eq!(client.escrows(&3, &10).len(), 0);
+}
@@ -0,0 +1,77 @@
+/*
+    Copyright (c) 2026 XBID LABS LLC
+
+    This file is part of XBID-AI project.
+    Licensed under the MIT License.
+    Author: Fred Kyung-jin Rezeau (오경진 吳景振) <hello@kyungj.in>
+*/
+
+use soroban_sdk::{contracterror, contractevent, contracttype, Address, BytesN};
+
+// Mirrors the escrow clock; both encode as the variant name.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[contracttype]
+pub enum Clock {
+    Ledger,
+    Timestamp,
+}
+
+// Arguments of the escrow initialize call. `origin` is a ledger
+// sequence or a unix timestamp depending on `clock`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[contracttype]
+pub struct EscrowConfig {
+    pub admin: Address,
+    pub token: Address,
+    pub fund: Address,
+    pub rewards: Address,
+    pub clock: Clock,
+    pub origin: u64,
+}
+
+// Registry entry of a deployed escrow.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[contracttype]
+pub struct Deployment {
+    pub index: u32,
+    pub deployer: Address,
+    pub salt: BytesN<32>,
+    pub wasm_hash: BytesN<32>,
+    pub config: EscrowConfig,
+    pub ledger: u32,
+}
+
+#[contractevent]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Deployed {
+    #[topic]
+    pub deployer: Address,
+    pub escrow: Address,
+    pub index: u32,
+    pub wasm_hash: BytesN<32>,
+}
+
+#[contractevent]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WasmUpdated {
+    pub wasm_hash: BytesN<32>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[contracterror]
+pub enum Error {
+    AlreadyInitialized = 1,
+    AlreadyDeployed = 2,
+    InvalidOrigin = 3,
+    NotFound = 4,
+}
+
+#[derive(Clone)]
+#[contracttype]
+pub enum Storage {
+    Admin,
+    WasmHash,
+    Count,
+    Index(u32),
+    Escrow(Address),
+}